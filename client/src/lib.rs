@@ -1,161 +1,278 @@
-use logic::card::{Card, Rank};
+use std::cmp::Ordering;
+
+use logic::Deck;
+use logic::betting::{Bankroll, Bet, settle};
+use logic::card::Card;
+use logic::counting::{Counter, CountingSystem};
+use logic::error::GameError;
+use logic::game::{Game, Hand, Outcome};
+use logic::rules::Rules;
+use logic::strategy::Strategy;
 use reqwest::get;
 use serde::{Deserialize, Serialize};
-type Deck = Vec<Card>;
 
 async fn fetch_deck(url: String) -> reqwest::Result<Deck> {
     get(url).await?.json::<Deck>().await
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HandResult {
+    hand: Vec<Card>,
+    score: u8,
+    soft: bool,
+    doubled: bool,
+    /// Not set for the dealer's hand.
+    outcome: Option<Outcome>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlayerResult {
     name: String,
-    hand: Deck,
-    score: u8,
+    /// More than one hand after splitting.
+    hands: Vec<HandResult>,
+    /// What the player won or lost on their bet, negative for a loss. Not set without a bet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    net: Option<f64>,
+    /// The player's bankroll after the round, when they brought one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bankroll: Option<f64>,
+}
+
+/// The count after every card of the game has been seen.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CountResult {
+    system: CountingSystem,
+    running: i32,
+    true_count: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GameResult {
     deck: Deck,
     players: Vec<PlayerResult>,
-    winner: String,
+    /// Whoever won the most hands, nobody when the player won as many as they lost.
+    winner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    count: Option<CountResult>,
 }
 
-pub trait ToInt {
-    fn to_int(&self) -> u8;
+/// What a player puts on the table: `bet` on their starting hand, out of `bankroll` if given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stake {
+    pub bet: Bet,
+    pub bankroll: Option<Bankroll>,
 }
 
-impl ToInt for Card {
-    fn to_int(&self) -> u8 {
-        self.value.to_int()
-    }
+pub struct Player {
+    name: String,
+    strategy: Box<dyn Strategy>,
+    stake: Option<Stake>,
 }
 
-impl ToInt for Rank {
-    fn to_int(&self) -> u8 {
-        match &self {
-            Rank::Two => 2,
-            Rank::Three => 3,
-            Rank::Four => 4,
-            Rank::Five => 5,
-            Rank::Six => 6,
-            Rank::Seven => 7,
-            Rank::Eight => 8,
-            Rank::Nine => 9,
-            Rank::Ace => 11,
-            _ => 10,
+impl Player {
+    pub fn new(name: String, strategy: Box<dyn Strategy>, stake: Option<Stake>) -> Self {
+        Player {
+            name,
+            strategy,
+            stake,
         }
     }
 }
 
-trait Scoreable {
-    fn score(&self) -> u8;
-}
-trait Blackjack<S: Scoreable = Self> {
-    fn has_blackjack(&self) -> bool;
-}
-trait Bust<S: Scoreable = Self> {
-    fn is_bust(&self) -> bool;
-}
-
-trait Draw {
-    fn hit_me(&self, other_score: u8) -> bool;
+fn to_result(hand: &Hand, doubled: bool, outcome: Option<Outcome>) -> HandResult {
+    let value = hand.value();
+    HandResult {
+        hand: hand.cards().to_vec(),
+        score: value.total,
+        soft: value.soft,
+        doubled,
+        outcome,
+    }
 }
 
-trait ToPlayerResult {
-    fn to_result(&self) -> PlayerResult;
-}
+fn play_game(
+    deck: Deck,
+    player: Player,
+    rules: Rules,
+    counting: Option<CountingSystem>,
+) -> Result<GameResult, GameError> {
+    println!("Playing as {:?}", player.name);
+    println!("{:#?}", deck);
+    let dealer_name = "Dealer";
+    let mut game = Game::new(deck.clone(), rules, 1)?;
+    game.play(player.strategy.as_ref())?;
 
-pub struct Player {
-    name: String,
-    hand: Vec<Card>,
-    strategy: Box<dyn Fn(u8, u8) -> bool>,
-}
-impl ToPlayerResult for Player {
-    fn to_result(&self) -> PlayerResult {
-        PlayerResult {
-            name: self.name.clone(),
-            hand: self.hand.clone(),
-            score: self.score(),
+    let round = game.round();
+    let hands = round.seats()[0]
+        .hands()
+        .iter()
+        .map(|hand| to_result(hand.hand(), hand.is_doubled(), hand.outcome()))
+        .collect::<Vec<HandResult>>();
+    let won = hands
+        .iter()
+        .filter(|hand| matches!(hand.outcome, Some(Outcome::PlayerWin | Outcome::Blackjack)))
+        .count();
+    let lost = hands
+        .iter()
+        .filter(|hand| matches!(hand.outcome, Some(Outcome::DealerWin | Outcome::Surrender)))
+        .count();
+    let winner = match won.cmp(&lost) {
+        Ordering::Greater => Some(player.name.clone()),
+        Ordering::Less => Some(dealer_name.into()),
+        Ordering::Equal => None,
+    };
+    let settlement = player.stake.and_then(|stake| settle(round, 0, stake.bet));
+    let bankroll = player.stake.and_then(|stake| {
+        let mut bankroll = stake.bankroll?;
+        bankroll.apply(&settlement?);
+        Some(bankroll.balance())
+    });
+    let count = counting.map(|system| {
+        let mut counter = Counter::new(system, deck.decks());
+        counter.see_round(round);
+        CountResult {
+            system,
+            running: counter.running(),
+            true_count: counter.true_count(),
         }
-    }
-}
-impl Scoreable for Player {
-    fn score(&self) -> u8 {
-        self.hand.iter().map(|x| x.to_int()).sum()
-    }
-}
-impl Draw for Player {
-    fn hit_me(&self, other_score: u8) -> bool {
-        (self.strategy)(self.score(), other_score)
-    }
-}
-impl Blackjack for Player {
-    fn has_blackjack(&self) -> bool {
-        self.hand.len() == 2 && self.score() == 21
-    }
-}
-impl Bust for Player {
-    fn is_bust(&self) -> bool {
-        self.score() > 21
-    }
+    });
+    Ok(GameResult {
+        deck,
+        players: vec![
+            PlayerResult {
+                name: player.name,
+                hands,
+                net: settlement.map(|settlement| settlement.net),
+                bankroll,
+            },
+            PlayerResult {
+                name: dealer_name.into(),
+                hands: vec![to_result(round.dealer(), false, None)],
+                net: None,
+                bankroll: None,
+            },
+        ],
+        winner,
+        count,
+    })
 }
 
-fn player_wins(original: Deck, player: Player, dealer: Player) -> GameResult {
-    GameResult {
-        winner: player.name.clone(),
-        players: vec![player.to_result(), dealer.to_result()],
-        deck: original,
-    }
+/// Plays a round from the deck at `url`. Fails when the deck runs out or the table refuses
+/// an action of the player's strategy.
+pub async fn play_blackjack(
+    url: String,
+    player: Player,
+    rules: Rules,
+    counting: Option<CountingSystem>,
+) -> Result<GameResult, GameError> {
+    let deck = fetch_deck(url).await.expect("Could not parse deck");
+    play_game(deck, player, rules, counting)
 }
 
-fn dealer_wins(original: Deck, player: Player, dealer: Player) -> GameResult {
-    GameResult {
-        winner: dealer.name.clone(),
-        players: vec![player.to_result(), dealer.to_result()],
-        deck: original,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic::card::parse_cards;
+    use logic::deck_generator::{
+        both_blackjack, complete_deck, four_aces, player_blackjack, tie21,
+    };
+    use logic::game::Action;
+    use logic::strategy::{BasicStrategy, Threshold};
+
+    fn sam(stake: Option<Stake>) -> Player {
+        Player::new("Sam".into(), Box::new(Threshold::default()), stake)
     }
-}
 
-fn play_game(deck: Deck, player_name: String) -> GameResult {
-    println!("Playing as {:?}", player_name);
-    println!("{:#?}", deck);
-    let original = deck.clone();
-    let mut playing_deck = deck;
-    let starting_hand = vec![playing_deck.remove(0), playing_deck.remove(0)];
-    let mut player = Player {
-        name: player_name,
-        hand: starting_hand,
-        strategy: Box::new(|own_score, _| own_score < 17),
-    };
-    let dealer_hand = vec![playing_deck.remove(0), playing_deck.remove(0)];
-    let mut dealer = Player {
-        name: "Dealer".into(),
-        hand: dealer_hand,
-        strategy: Box::new(|own_score, player_score| own_score <= player_score),
-    };
-    if dealer.has_blackjack() || player.is_bust() {
-        return dealer_wins(original, player, dealer);
-    } else if player.has_blackjack() || dealer.is_bust() {
-        return player_wins(original, player, dealer);
+    #[test]
+    fn four_aces_are_scored_as_soft_hands_not_a_bust() {
+        let result = play_game(four_aces(1), sam(None), Rules::interview(), None).unwrap();
+        let player = &result.players[0].hands[0];
+
+        assert_eq!(player.hand.len(), 4);
+        assert_eq!(player.score, 17);
+        assert!(player.soft);
+        assert_eq!(result.winner.as_deref(), Some("Dealer"));
     }
-    while player.hit_me(dealer.score()) {
-        player.hand.push(playing_deck.remove(0));
+
+    #[test]
+    fn the_dealer_follows_the_table_rules() {
+        let interview = play_game(tie21(1), sam(None), Rules::interview(), None).unwrap();
+        assert_eq!(interview.players[1].hands[0].hand.len(), 4);
+
+        let casino = play_game(both_blackjack(1), sam(None), Rules::casino(), None).unwrap();
+        assert_eq!(casino.players[0].hands[0].outcome, Some(Outcome::Push));
+        assert_eq!(casino.winner, None);
     }
-    if player.is_bust() {
-        return dealer_wins(original, player, dealer);
+
+    #[test]
+    fn players_with_a_stake_get_their_net_and_bankroll() {
+        let stake = Stake {
+            bet: Bet::new(10.0).unwrap(),
+            bankroll: Some(Bankroll::new(100.0).unwrap()),
+        };
+        let result =
+            play_game(player_blackjack(1), sam(Some(stake)), Rules::casino(), None).unwrap();
+        assert_eq!(result.players[0].net, Some(15.0));
+        assert_eq!(result.players[0].bankroll, Some(115.0));
+
+        let result = play_game(four_aces(1), sam(None), Rules::interview(), None).unwrap();
+        assert_eq!(result.players[0].net, None);
     }
-    let p_score = player.score();
-    while dealer.hit_me(p_score) {
-        dealer.hand.push(playing_deck.remove(0));
+
+    #[test]
+    fn the_count_covers_every_card_dealt() {
+        let result = play_game(
+            four_aces(1),
+            sam(None),
+            Rules::interview(),
+            Some(CountingSystem::HiLo),
+        )
+        .unwrap();
+        let count = result.count.unwrap();
+        let dealt = result
+            .players
+            .iter()
+            .flat_map(|player| &player.hands)
+            .map(|hand| hand.hand.len())
+            .sum::<usize>();
+
+        let mut counter = Counter::new(CountingSystem::HiLo, 1);
+        counter.see_all(&four_aces(1).cards()[..dealt]);
+        assert_eq!(count.running, counter.running());
+        assert_eq!(count.true_count, counter.true_count());
     }
-    if dealer.is_bust() {
-        return player_wins(original, player, dealer);
+
+    #[test]
+    fn players_can_play_basic_strategy() {
+        let deck = complete_deck(parse_cards("S8,H8,C10,D7,S3,DK").unwrap(), 1).unwrap();
+        let rules = Rules::casino();
+        let player = Player::new(
+            "Sam".into(),
+            Box::new(BasicStrategy::for_rules(&rules)),
+            None,
+        );
+        let result = play_game(deck, player, rules, None).unwrap();
+
+        let hands = &result.players[0].hands;
+        assert_eq!(hands.len(), 2);
+        assert!(hands[0].doubled);
+        assert_eq!(hands[0].score, 21);
     }
-    dealer_wins(original, player, dealer)
-}
 
-pub async fn play_blackjack(url: String, player_name: String) -> GameResult {
-    let deck = fetch_deck(url).await.expect("Could not parse deck");
-    play_game(deck, player_name)
+    #[test]
+    fn a_strategy_the_table_refuses_is_an_error() {
+        struct AlwaysDouble;
+        impl Strategy for AlwaysDouble {
+            fn decide(&self, _: &Hand, _: &Card, _: &[Action]) -> Action {
+                Action::Double
+            }
+        }
+
+        let player = Player::new("Sam".into(), Box::new(AlwaysDouble), None);
+        let result = play_game(tie21(1), player, Rules::interview(), None);
+        assert_eq!(
+            result.err(),
+            Some(GameError::ActionNotAllowed(Action::Double))
+        );
+    }
 }
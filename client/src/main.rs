@@ -1,9 +1,101 @@
-use clap::Parser;
-use client::play_blackjack;
+use clap::{Parser, Subcommand, ValueEnum};
+use client::{Player, Stake, play_blackjack};
+use logic::betting::{Bankroll, Bet};
+use logic::counting::CountingSystem;
+use logic::deck_generator::{MAX_DECKS, random_seed};
+use logic::rules::Rules;
+use logic::simulate::{BetSpread, Simulation, simulate};
+use logic::strategy::{BasicStrategy, MimicDealer, NeverBust, Strategy, Threshold};
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum TableRules {
+    /// Dealer draws until beating the player, dealer wins ties
+    Interview,
+    /// Dealer stands on 17, ties push
+    Casino,
+    /// Dealer hits soft 17, ties push
+    CasinoH17,
+}
+
+impl From<TableRules> for Rules {
+    fn from(rules: TableRules) -> Self {
+        match rules {
+            TableRules::Interview => Rules::interview(),
+            TableRules::Casino => Rules::casino(),
+            TableRules::CasinoH17 => Rules::casino_h17(),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum PlayerStrategy {
+    /// Hit below 17
+    Threshold17,
+    /// Hit below 17, and on soft 17 when the dealer does
+    MimicDealer,
+    /// Only hit when the next card can't bust the hand
+    NeverBust,
+    /// Basic strategy chart for the table's soft 17 rule
+    Basic,
+}
+
+impl PlayerStrategy {
+    fn for_rules(&self, rules: &Rules) -> Box<dyn Strategy + Send + Sync> {
+        match self {
+            PlayerStrategy::Threshold17 => Box::new(Threshold::default()),
+            PlayerStrategy::MimicDealer => Box::new(MimicDealer::for_rules(rules)),
+            PlayerStrategy::NeverBust => Box::new(NeverBust),
+            PlayerStrategy::Basic => Box::new(BasicStrategy::for_rules(rules)),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CountSystem {
+    /// 2-6 +1, 10-A -1
+    HiLo,
+    /// Knock-Out, 2-7 +1, 10-A -1
+    Ko,
+    /// 2,3,7 +1, 4-6 +2, 9 -1, 10 -2
+    OmegaIi,
+}
+
+impl From<CountSystem> for CountingSystem {
+    fn from(system: CountSystem) -> Self {
+        match system {
+            CountSystem::HiLo => CountingSystem::HiLo,
+            CountSystem::Ko => CountingSystem::Ko,
+            CountSystem::OmegaIi => CountingSystem::OmegaII,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Play rounds offline from seeded shoes and report win rates and the house edge
+    Simulate {
+        #[clap(short = 'n', long, default_value_t = 1_000_000)]
+        rounds: u64,
+        #[clap(short, long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(1..=MAX_DECKS as u64))]
+        decks: u64,
+        /// Seed for the shoes, picked at random when not given
+        #[clap(long)]
+        seed: Option<u64>,
+        /// Share of the shoe dealt before reshuffling
+        #[clap(long, default_value_t = 0.75)]
+        penetration: f64,
+        /// Bet one unit up to a true count of 2, then a unit more per true count up to this
+        /// many units, using the --count system. KO bets off its running count instead
+        #[clap(long, requires = "count", value_parser = clap::value_parser!(u32).range(1..))]
+        spread: Option<u32>,
+    },
+}
 
 #[derive(Parser, Debug)]
 #[clap(version, about)]
 pub struct ClientArgs {
+    #[clap(subcommand)]
+    command: Option<Command>,
     #[clap(
         short,
         long,
@@ -12,11 +104,87 @@ pub struct ClientArgs {
     url: String,
     #[clap(short, long, default_value = "Sam")]
     player_name: String,
+    #[clap(short, long, global = true, value_enum, default_value_t = TableRules::Interview)]
+    rules: TableRules,
+    #[clap(short, long, global = true, value_enum, default_value_t = PlayerStrategy::Threshold17)]
+    strategy: PlayerStrategy,
+    /// Amount to bet on the starting hand, settled with the table's payouts
+    #[clap(short, long, value_parser = parse_bet)]
+    bet: Option<Bet>,
+    /// Counting system to keep the count with
+    #[clap(short, long, global = true, value_enum)]
+    count: Option<CountSystem>,
+    /// Bankroll to settle the bet against, needs --bet
+    #[clap(long, requires = "bet", value_parser = parse_bankroll)]
+    bankroll: Option<Bankroll>,
+}
+
+fn parse_bet(bet: &str) -> Result<Bet, String> {
+    let amount = bet.parse::<f64>().map_err(|e| e.to_string())?;
+    Bet::new(amount).map_err(|e| e.to_string())
+}
+
+fn parse_bankroll(bankroll: &str) -> Result<Bankroll, String> {
+    let balance = bankroll.parse::<f64>().map_err(|e| e.to_string())?;
+    Bankroll::new(balance).map_err(|e| e.to_string())
 }
 
 #[tokio::main]
 async fn main() {
     let client_args = ClientArgs::parse();
-    let result = play_blackjack(client_args.url, client_args.player_name).await;
-    println!("{:#?}", result)
+    if let Some(Command::Simulate {
+        rounds,
+        decks,
+        seed,
+        penetration,
+        spread,
+    }) = client_args.command
+    {
+        let rules = client_args.rules.into();
+        let seed = seed.unwrap_or_else(random_seed);
+        let spread = spread
+            .zip(client_args.count)
+            .map(|(max_units, system)| BetSpread::new(system.into(), max_units))
+            .transpose();
+        let simulation = spread.and_then(|spread| {
+            let mut simulation = Simulation::new(rules, decks as usize, rounds, seed)?
+                .with_penetration(penetration)?;
+            simulation.spread = spread;
+            Ok(simulation)
+        });
+        let simulation = match simulation {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        };
+        println!("Simulating {} rounds with seed {}", rounds, seed);
+        match simulate(&simulation, client_args.strategy.for_rules(&rules).as_ref()) {
+            Ok(report) => println!("{:#?}", report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let stake = client_args.bet.map(|bet| Stake {
+        bet,
+        bankroll: client_args.bankroll,
+    });
+    let rules = client_args.rules.into();
+    let player = Player::new(
+        client_args.player_name,
+        client_args.strategy.for_rules(&rules),
+        stake,
+    );
+    let counting = client_args.count.map(Into::into);
+    match play_blackjack(client_args.url, player, rules, counting).await {
+        Ok(result) => println!("{:#?}", result),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 }
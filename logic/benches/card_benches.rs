@@ -1,20 +1,27 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{Criterion, criterion_group, criterion_main};
 use logic::{
     card::{Card, Rank, Suit},
     deck_generator::{
         complete_deck, dealer_blackjack, dealer_bust, four_aces, player_blackjack, player_bust,
-        shuffle,
+        shuffle, shuffle_seeded, shuffled_shoe,
     },
 };
+use std::hint::black_box;
 use strum::IntoEnumIterator;
 
 fn create_hand_benchmark(c: &mut Criterion) {
-    c.bench_function("dealer_blackjack", |b| b.iter(dealer_blackjack));
-    c.bench_function("dealer_bust", |b| b.iter(dealer_bust));
-    c.bench_function("four_aces", |b| b.iter(four_aces));
-    c.bench_function("player_blackjack", |b| b.iter(player_blackjack));
-    c.bench_function("player_bust", |b| b.iter(player_bust));
+    c.bench_function("dealer_blackjack", |b| b.iter(|| dealer_blackjack(1)));
+    c.bench_function("dealer_bust", |b| b.iter(|| dealer_bust(1)));
+    c.bench_function("four_aces", |b| b.iter(|| four_aces(1)));
+    c.bench_function("player_blackjack", |b| b.iter(|| player_blackjack(1)));
+    c.bench_function("player_bust", |b| b.iter(|| player_bust(1)));
     c.bench_function("shuffle", |b| b.iter(shuffle));
+    c.bench_function("shuffle_seeded", |b| {
+        b.iter(|| shuffle_seeded(black_box(1337)))
+    });
+    c.bench_function("shuffled_shoe:6", |b| {
+        b.iter(|| shuffled_shoe(black_box(6), black_box(1337)))
+    });
 }
 
 fn complete_deck_benchmark(c: &mut Criterion) {
@@ -28,19 +35,19 @@ fn complete_deck_benchmark(c: &mut Criterion) {
         .collect::<Vec<Card>>();
 
     c.bench_function("dealer_blackjack:10", |b| {
-        b.iter(|| complete_deck(black_box(cards[0..10].to_vec().clone())))
+        b.iter(|| complete_deck(black_box(cards[0..10].to_vec().clone()), 1).unwrap())
     });
     c.bench_function("dealer_blackjack:20", |b| {
-        b.iter(|| complete_deck(black_box(cards[0..20].to_vec().clone())))
+        b.iter(|| complete_deck(black_box(cards[0..20].to_vec().clone()), 1).unwrap())
     });
     c.bench_function("dealer_blackjack:30", |b| {
-        b.iter(|| complete_deck(black_box(cards[0..30].to_vec().clone())))
+        b.iter(|| complete_deck(black_box(cards[0..30].to_vec().clone()), 1).unwrap())
     });
     c.bench_function("dealer_blackjack:40", |b| {
-        b.iter(|| complete_deck(black_box(cards[0..40].to_vec().clone())))
+        b.iter(|| complete_deck(black_box(cards[0..40].to_vec().clone()), 1).unwrap())
     });
     c.bench_function("dealer_blackjack:50", |b| {
-        b.iter(|| complete_deck(black_box(cards[0..50].to_vec().clone())))
+        b.iter(|| complete_deck(black_box(cards[0..50].to_vec().clone()), 1).unwrap())
     });
 }
 
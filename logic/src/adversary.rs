@@ -0,0 +1,299 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
+
+use crate::Deck;
+use crate::card::Card;
+use crate::deck_generator::{complete_deck, deck_count, shoe};
+use crate::error::{AdversaryError, GameError};
+use crate::game::{Game, Hand, Outcome};
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+
+/// How many random decks a search tries before it gives up.
+pub const MAX_ATTEMPTS: usize = 100_000;
+
+/// How many cards of each searched deck are picked at random, the rest follows in standard
+/// order. More than a round of the interview game needs, even with the deck cut short.
+const FRONT_CARDS: usize = 16;
+
+/// How a one seat round played out: its outcome and the cards each side ended with. When the
+/// player splits, only their first hand counts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub outcome: Outcome,
+    pub player: Vec<Card>,
+    pub dealer: Vec<Card>,
+}
+
+/// Plays a one seat round from the top of a deck, like a candidate's client would.
+pub trait Referee {
+    fn referee(&self, deck: &Deck) -> Result<Verdict, GameError>;
+}
+
+/// The rules engine playing `rules`, with the player following `strategy`. The correct referee
+/// with the right strategy, a buggy client with a buggy one.
+#[derive(Debug, Clone, Copy)]
+pub struct Engine<'a, S: ?Sized> {
+    pub rules: Rules,
+    pub strategy: &'a S,
+}
+
+impl<S> Referee for Engine<'_, S>
+where
+    S: Strategy + ?Sized,
+{
+    fn referee(&self, deck: &Deck) -> Result<Verdict, GameError> {
+        let mut game = Game::new(deck.clone(), self.rules, 1)?;
+        game.play(self.strategy)?;
+        let round = game.round();
+        Ok(Verdict {
+            outcome: round.outcome(0, 0).expect("Round is settled"),
+            player: round.seats()[0].hands()[0].hand().cards().to_vec(),
+            dealer: round.dealer().cards().to_vec(),
+        })
+    }
+}
+
+/// Mistakes we have seen candidates make implementing the interview game. Each plays the game
+/// with the player hitting below 17, getting exactly one thing wrong.
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, EnumIter, EnumString, AsRefStr, Eq, PartialEq,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum BugModel {
+    /// Counts every ace as 11, so a soft hand that should drop to hard busts instead.
+    AceAlwaysEleven,
+    /// Counts every ace as 1, so there are no blackjacks and soft hands hit on.
+    AceAlwaysOne,
+    /// Deals player, dealer, player, dealer instead of two to the player and two to the dealer.
+    AlternatingDeal,
+    /// Lets the dealer stand on 17 like at a casino, instead of drawing until they beat the
+    /// player.
+    #[serde(rename = "dealer-stands-on-17")]
+    #[strum(serialize = "dealer-stands-on-17")]
+    DealerStandsOn17,
+    /// Has the dealer draw even when the player is already bust.
+    DealerDrawsAfterPlayerBust,
+    /// Plays on when a blackjack is dealt instead of ending the round.
+    NoBlackjackCheck,
+    /// Only keeps the first ten cards of the deck, and runs out when a round needs more.
+    FirstTenCards,
+}
+
+/// Every bug model we know of.
+pub fn catalog() -> impl Iterator<Item = BugModel> {
+    BugModel::iter()
+}
+
+impl BugModel {
+    fn score(&self, cards: &[Card]) -> u8 {
+        let points = cards.iter().map(|card| card.value.points());
+        match self {
+            BugModel::AceAlwaysEleven => points
+                .map(|points| if points == 1 { 11 } else { points })
+                .sum(),
+            BugModel::AceAlwaysOne => points.sum(),
+            _ => cards.iter().cloned().collect::<Hand>().total(),
+        }
+    }
+}
+
+impl Referee for BugModel {
+    fn referee(&self, deck: &Deck) -> Result<Verdict, GameError> {
+        let kept = match self {
+            BugModel::FirstTenCards => 10,
+            _ => deck.size(),
+        };
+        let mut cards = deck.cards().iter().take(kept).cloned();
+        let mut draw = || cards.next().ok_or(GameError::OutOfCards);
+        let (mut player, mut dealer) = (vec![], vec![]);
+        if *self == BugModel::AlternatingDeal {
+            for _ in 0..2 {
+                player.push(draw()?);
+                dealer.push(draw()?);
+            }
+        } else {
+            player.extend([draw()?, draw()?]);
+            dealer.extend([draw()?, draw()?]);
+        }
+        let verdict = |outcome, player, dealer| {
+            Ok(Verdict {
+                outcome,
+                player,
+                dealer,
+            })
+        };
+
+        if *self != BugModel::NoBlackjackCheck {
+            if self.score(&dealer) == 21 {
+                return verdict(Outcome::DealerWin, player, dealer);
+            }
+            if self.score(&player) == 21 {
+                return verdict(Outcome::Blackjack, player, dealer);
+            }
+        }
+        while self.score(&player) < 17 {
+            player.push(draw()?);
+        }
+        let player_total = self.score(&player);
+        if player_total > 21 && *self != BugModel::DealerDrawsAfterPlayerBust {
+            return verdict(Outcome::DealerWin, player, dealer);
+        }
+        let dealer_draws = |dealer: &[Card]| match self {
+            BugModel::DealerStandsOn17 => self.score(dealer) < 17,
+            _ => self.score(dealer) <= player_total,
+        };
+        while dealer_draws(&dealer) {
+            dealer.push(draw()?);
+        }
+        let dealer_total = self.score(&dealer);
+        let outcome = if player_total > 21 {
+            Outcome::DealerWin
+        } else if dealer_total > 21 || player_total > dealer_total {
+            Outcome::PlayerWin
+        } else {
+            Outcome::DealerWin
+        };
+        verdict(outcome, player, dealer)
+    }
+}
+
+/// A deck the two referees play differently.
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    pub deck: Deck,
+    pub expected: Verdict,
+    /// What the buggy referee made of the deck, or why it couldn't play it.
+    pub actual: Result<Verdict, GameError>,
+}
+
+/// Searches decks of `decks` decks for one where `buggy` plays the round differently from
+/// `correct`, trying at most [`MAX_ATTEMPTS`] decks. The same seed finds the same deck. Fails
+/// when `decks` isn't a shoe that can be dealt, or when `correct` can't play a deck itself, it
+/// is no good as a reference then.
+///
+/// Each deck starts with random cards picked by point value first, so aces and low cards turn
+/// up as often as tens, followed by the rest of the shoe.
+pub fn find_disagreement<B, C>(
+    buggy: &B,
+    correct: &C,
+    decks: usize,
+    seed: u64,
+) -> Result<Option<Disagreement>, AdversaryError>
+where
+    B: Referee + ?Sized,
+    C: Referee + ?Sized,
+{
+    let decks = deck_count(Some(decks))?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..MAX_ATTEMPTS {
+        let deck = random_front(&mut rng, decks);
+        let expected = correct.referee(&deck)?;
+        let actual = buggy.referee(&deck);
+        if actual.as_ref() != Ok(&expected) {
+            return Ok(Some(Disagreement {
+                deck,
+                expected,
+                actual,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+fn random_front<R: Rng + ?Sized>(rng: &mut R, decks: usize) -> Deck {
+    let mut by_points: [Vec<Card>; 10] = Default::default();
+    for card in shoe(decks).cards() {
+        by_points[usize::from(card.value.points() - 1)].push(card.clone());
+    }
+    let front = (0..FRONT_CARDS)
+        .map(|_| {
+            let left: Vec<usize> = (0..10).filter(|&i| !by_points[i].is_empty()).collect();
+            let cards = &mut by_points[left[rng.random_range(0..left.len())]];
+            cards.swap_remove(rng.random_range(0..cards.len()))
+        })
+        .collect();
+    complete_deck(front, decks).expect("Front only uses cards of the shoe")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_cards;
+    use crate::deck_generator::MAX_DECKS;
+    use crate::error::DeckError;
+    use crate::rules::Rules;
+    use crate::strategy::Threshold;
+
+    const INTERVIEW_PLAYER: Threshold = Threshold { stand_on: 17 };
+
+    fn reference() -> Engine<'static, Threshold> {
+        Engine {
+            rules: Rules::interview(),
+            strategy: &INTERVIEW_PLAYER,
+        }
+    }
+
+    #[test]
+    fn every_bug_model_is_caught() {
+        for bug in catalog() {
+            let found = find_disagreement(&bug, &reference(), 1, 7)
+                .unwrap()
+                .unwrap_or_else(|| panic!("No deck catches {}", bug.as_ref()));
+            assert_eq!(reference().referee(&found.deck), Ok(found.expected.clone()));
+            assert_eq!(bug.referee(&found.deck), found.actual);
+            assert_ne!(found.actual, Ok(found.expected));
+        }
+    }
+
+    #[test]
+    fn a_fixed_eleven_ace_busts_a_soft_hand() {
+        let cards = parse_cards("SA,S5,HK,H8,C10,D5").unwrap();
+        let deck = complete_deck(cards, 1).unwrap();
+        let correct = reference().referee(&deck).unwrap();
+        let buggy = BugModel::AceAlwaysEleven.referee(&deck).unwrap();
+
+        // Soft 16 takes a ten to hard 16 and hits on to 21, a fixed ace makes that 26
+        assert_eq!(correct.player.iter().cloned().collect::<Hand>().total(), 21);
+        assert_eq!(buggy.player.len(), 3);
+        assert_eq!(buggy.outcome, Outcome::DealerWin);
+        assert_ne!(correct.outcome, Outcome::DealerWin);
+    }
+
+    #[test]
+    fn buggy_strategies_are_caught_too() {
+        let timid = Engine {
+            rules: Rules::interview(),
+            strategy: &Threshold { stand_on: 12 },
+        };
+        let found = find_disagreement(&timid, &reference(), 2, 1)
+            .unwrap()
+            .unwrap();
+        let actual = found.actual.unwrap();
+        assert!(actual.player.len() < found.expected.player.len());
+    }
+
+    #[test]
+    fn a_reference_that_cant_play_fails_the_search() {
+        let result = find_disagreement(&reference(), &BugModel::FirstTenCards, 1, 7);
+        assert_eq!(
+            result.err(),
+            Some(AdversaryError::Game(GameError::OutOfCards))
+        );
+    }
+
+    #[test]
+    fn shoes_that_cant_be_dealt_fail_the_search() {
+        for decks in [0, MAX_DECKS + 1] {
+            let result = find_disagreement(&BugModel::AceAlwaysOne, &reference(), decks, 7);
+            assert_eq!(
+                result.err(),
+                Some(AdversaryError::Deck(DeckError::InvalidDeckCount(decks)))
+            );
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, Rank, Suit};
+use crate::deck_generator::complete_deck;
+use crate::error::AnalysisError;
+use crate::game::{Outcome, Round};
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+
+/// Only a card's points matter to a round, so the unknown cards are dealt as one stand-in card
+/// per point value. That keeps rounds that differ only in suits or face cards the same state.
+const STAND_INS: [Rank; 10] = [
+    Rank::Ace,
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+];
+
+/// How many distinct states an analysis may go through before it gives up.
+pub const MAX_STATES: usize = 200_000;
+
+/// The exact probability of each outcome for the player. When the player splits, each of
+/// their hands gets an equal share of the round's probability.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct Analysis {
+    pub player_win: f64,
+    pub dealer_win: f64,
+    pub push: f64,
+    pub blackjack: f64,
+    pub surrender: f64,
+}
+
+impl Analysis {
+    pub fn probability(&self, outcome: Outcome) -> f64 {
+        match outcome {
+            Outcome::PlayerWin => self.player_win,
+            Outcome::DealerWin => self.dealer_win,
+            Outcome::Push => self.push,
+            Outcome::Blackjack => self.blackjack,
+            Outcome::Surrender => self.surrender,
+        }
+    }
+
+    /// The outcome, if the cards leave only one possible. Probabilities summed over several
+    /// unknown cards can fall short of exactly 1, so this looks for the only outcome that
+    /// can happen at all rather than one with a probability of 1.
+    pub fn certain(&self) -> Option<Outcome> {
+        let mut possible = [
+            Outcome::PlayerWin,
+            Outcome::DealerWin,
+            Outcome::Push,
+            Outcome::Blackjack,
+            Outcome::Surrender,
+        ]
+        .into_iter()
+        .filter(|outcome| self.probability(*outcome) > 0.0);
+        match (possible.next(), possible.next()) {
+            (Some(outcome), None) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    fn probability_mut(&mut self, outcome: Outcome) -> &mut f64 {
+        match outcome {
+            Outcome::PlayerWin => &mut self.player_win,
+            Outcome::DealerWin => &mut self.dealer_win,
+            Outcome::Push => &mut self.push,
+            Outcome::Blackjack => &mut self.blackjack,
+            Outcome::Surrender => &mut self.surrender,
+        }
+    }
+
+    fn add_scaled(&mut self, other: &Analysis, weight: f64) {
+        self.player_win += other.player_win * weight;
+        self.dealer_win += other.dealer_win * weight;
+        self.push += other.push * weight;
+        self.blackjack += other.blackjack * weight;
+        self.surrender += other.surrender * weight;
+    }
+}
+
+/// Analyzes a one seat round dealt from `decks` decks that start with `front`, in that order,
+/// followed by every possible ordering of the rest. The player plays `strategy`.
+///
+/// Orderings that only differ in suits, or that bring the same cards in a different order,
+/// lead to the same state and are worked out once. Fails when more than [`MAX_STATES`] are
+/// left after that, which takes a good part of the deck being unknown.
+pub fn analyze<S>(
+    front: &[Card],
+    decks: usize,
+    rules: Rules,
+    strategy: &S,
+) -> Result<Analysis, AnalysisError>
+where
+    S: Strategy + ?Sized,
+{
+    let deck = complete_deck(front.to_vec(), decks)?;
+    let mut rest = [0u8; 10];
+    for card in &deck.cards()[front.len()..] {
+        rest[usize::from(card.value.points() - 1)] += 1;
+    }
+    let mut analyzer = Analyzer {
+        front,
+        strategy,
+        seen: HashMap::new(),
+    };
+    analyzer.analyze(Round::new(rules, 1), 0, rest)
+}
+
+/// A round, how many of the front cards it has used and how many unknown cards are left of
+/// each point value.
+type State = (Round, usize, [u8; 10]);
+
+struct Analyzer<'a, S: ?Sized> {
+    front: &'a [Card],
+    strategy: &'a S,
+    seen: HashMap<State, Analysis>,
+}
+
+impl<S> Analyzer<'_, S>
+where
+    S: Strategy + ?Sized,
+{
+    fn analyze(
+        &mut self,
+        round: Round,
+        used: usize,
+        rest: [u8; 10],
+    ) -> Result<Analysis, AnalysisError> {
+        let state = (round.normalized(), used, rest);
+        if let Some(analysis) = self.seen.get(&state) {
+            return Ok(*analysis);
+        }
+        if self.seen.len() >= MAX_STATES {
+            return Err(AnalysisError::TooManyStates(MAX_STATES));
+        }
+        let (round, used, rest) = state.clone();
+        let analysis = self.step(round, used, rest)?;
+        self.seen.insert(state, analysis);
+        Ok(analysis)
+    }
+
+    fn step(
+        &mut self,
+        mut round: Round,
+        used: usize,
+        rest: [u8; 10],
+    ) -> Result<Analysis, AnalysisError> {
+        let Some(draw) = round.play_until_draw(self.strategy)? else {
+            return Ok(settled(&round));
+        };
+        self.each_card(used, rest, |card| {
+            let mut round = round.clone();
+            round
+                .take(draw, card)
+                .expect("Round takes the card it asked for");
+            round
+        })
+    }
+
+    /// Averages over the next card: the next front card if any are left, otherwise every
+    /// unknown point value weighted by how many of it are left.
+    fn each_card<F>(
+        &mut self,
+        used: usize,
+        rest: [u8; 10],
+        mut next: F,
+    ) -> Result<Analysis, AnalysisError>
+    where
+        F: FnMut(Card) -> Round,
+    {
+        if let Some(card) = self.front.get(used) {
+            return self.analyze(next(card.clone()), used + 1, rest);
+        }
+        let left: u32 = rest.iter().map(|count| u32::from(*count)).sum();
+        let mut analysis = Analysis::default();
+        for (index, count) in rest.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let mut after = rest;
+            after[index] -= 1;
+            let card = Card {
+                suit: Suit::Spades,
+                value: STAND_INS[index].clone(),
+            };
+            let outcome = self.analyze(next(card), used, after)?;
+            analysis.add_scaled(&outcome, f64::from(*count) / f64::from(left));
+        }
+        Ok(analysis)
+    }
+}
+
+fn settled(round: &Round) -> Analysis {
+    let hands = round.seats()[0].hands();
+    let mut analysis = Analysis::default();
+    for hand in hands {
+        let outcome = hand.outcome().expect("Round is settled");
+        *analysis.probability_mut(outcome) += 1.0 / hands.len() as f64;
+    }
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_cards;
+    use crate::error::{DeckError, GameError};
+    use crate::game::{Action, Hand};
+    use crate::strategy::{BasicStrategy, Threshold};
+
+    fn interview(cards: &str) -> Analysis {
+        let cards = parse_cards(cards).unwrap();
+        analyze(&cards, 1, Rules::interview(), &Threshold::default()).unwrap()
+    }
+
+    fn total(analysis: &Analysis) -> f64 {
+        analysis.player_win
+            + analysis.dealer_win
+            + analysis.push
+            + analysis.blackjack
+            + analysis.surrender
+    }
+
+    #[test]
+    fn scenarios_are_certain() {
+        use crate::deck_generator::{four_aces, player_blackjack, tie21};
+
+        let rules = Rules::interview();
+        let strategy = Threshold::default();
+        let analyze = |deck: crate::Deck| analyze(deck.cards(), 1, rules, &strategy).unwrap();
+
+        assert_eq!(analyze(four_aces(1)).certain(), Some(Outcome::DealerWin));
+        assert_eq!(
+            analyze(player_blackjack(1)).certain(),
+            Some(Outcome::Blackjack)
+        );
+        assert_eq!(analyze(tie21(1)).certain(), Some(Outcome::PlayerWin));
+    }
+
+    #[test]
+    fn one_unknown_card_is_counted_exactly() {
+        // The player stands on 20 and a casino dealer hits 16 once, from the 48 cards left.
+        let cards = parse_cards("SK,HQ,CK,D6").unwrap();
+        let strategy = Threshold::default();
+        let analysis = analyze(&cards, 1, Rules::casino(), &strategy).unwrap();
+
+        assert!((analysis.player_win - 40.0 / 48.0).abs() < 1e-12);
+        assert!((analysis.push - 4.0 / 48.0).abs() < 1e-12);
+        assert!((analysis.dealer_win - 4.0 / 48.0).abs() < 1e-12);
+        assert_eq!(analysis.certain(), None);
+    }
+
+    #[test]
+    fn every_ordering_of_the_rest_adds_up() {
+        let analysis = interview("SK,H7");
+        assert!((total(&analysis) - 1.0).abs() < 1e-9);
+        assert!(analysis.dealer_win > analysis.player_win);
+
+        let casino = Rules::casino();
+        let cards = parse_cards("S8,H8,C6,D10,S3,H2").unwrap();
+        let strategy = BasicStrategy::for_rules(&casino);
+        let split = analyze(&cards, 1, casino, &strategy).unwrap();
+        assert!((total(&split) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_outcome_that_sums_to_almost_one_is_certain() {
+        let almost_one = Analysis {
+            dealer_win: 0.9999999999999999,
+            ..Analysis::default()
+        };
+        assert_eq!(almost_one.certain(), Some(Outcome::DealerWin));
+        assert_eq!(Analysis::default().certain(), None);
+    }
+
+    #[test]
+    fn a_strategy_the_table_refuses_is_an_error() {
+        struct AlwaysSplit;
+        impl Strategy for AlwaysSplit {
+            fn decide(&self, _: &Hand, _: &Card, _: &[Action]) -> Action {
+                Action::Split
+            }
+        }
+
+        let cards = parse_cards("SK,H7,C10,D9").unwrap();
+        assert_eq!(
+            analyze(&cards, 1, Rules::interview(), &AlwaysSplit),
+            Err(AnalysisError::Game(GameError::ActionNotAllowed(
+                Action::Split
+            )))
+        );
+    }
+
+    #[test]
+    fn a_mostly_unknown_deck_is_too_much() {
+        let casino = Rules::casino();
+        let strategy = BasicStrategy::for_rules(&casino);
+        assert_eq!(
+            analyze(&[], 1, casino, &strategy),
+            Err(AnalysisError::TooManyStates(MAX_STATES))
+        );
+    }
+
+    #[test]
+    fn cards_beyond_the_deck_are_rejected() {
+        let cards = parse_cards("SA,SA").unwrap();
+        let result = analyze(&cards, 1, Rules::interview(), &Threshold::default());
+        assert!(matches!(
+            result,
+            Err(AnalysisError::Deck(DeckError::DuplicateCards { .. }))
+        ));
+    }
+}
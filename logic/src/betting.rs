@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::BetError;
+use crate::game::{Outcome, PlayerHand, Round, Seat};
+use crate::rules::Rules;
+
+/// What a player bets on their starting hand, always a positive, finite amount, so a settlement
+/// can't pay the player for losing.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Bet(f64);
+
+impl Bet {
+    pub fn new(amount: f64) -> Result<Self, BetError> {
+        if amount.is_finite() && amount > 0.0 {
+            Ok(Bet(amount))
+        } else {
+            Err(BetError::InvalidBet(amount))
+        }
+    }
+
+    /// A bet of `units` units, at least one.
+    pub fn units(units: u32) -> Self {
+        Bet(f64::from(units.max(1)))
+    }
+
+    pub fn amount(&self) -> f64 {
+        self.0
+    }
+}
+
+/// The money side of a player's round.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct Settlement {
+    /// Everything the player put on the table: the bet on every hand, doubles and insurance.
+    pub wagered: f64,
+    /// What the player won, negative when they lost.
+    pub net: f64,
+}
+
+/// Settles the player in `seat` for a round where they bet `bet` on their starting hand.
+/// Split hands carry the same bet, doubles twice the bet and insurance costs half the bet.
+/// `None` until the round is settled.
+pub fn settle(round: &Round, seat: usize, bet: Bet) -> Option<Settlement> {
+    let bet = bet.amount();
+    let seat = round.seats().get(seat)?;
+    let hands = seat
+        .hands()
+        .iter()
+        .map(|hand| Some((stake(hand), hand_net(hand, round.rules())?)))
+        .collect::<Option<Vec<(f64, f64)>>>()?;
+    let (insurance_stake, insurance_net) = insurance(seat, round);
+    Some(Settlement {
+        wagered: bet * (hands.iter().map(|(stake, _)| stake).sum::<f64>() + insurance_stake),
+        net: bet * (hands.iter().map(|(_, net)| net).sum::<f64>() + insurance_net),
+    })
+}
+
+fn stake(hand: &PlayerHand) -> f64 {
+    if hand.is_doubled() { 2.0 } else { 1.0 }
+}
+
+/// What a settled hand won, in units of the starting bet.
+fn hand_net(hand: &PlayerHand, rules: &Rules) -> Option<f64> {
+    let net = match hand.outcome()? {
+        Outcome::Blackjack => rules.blackjack_payout.pays as f64 / rules.blackjack_payout.to as f64,
+        Outcome::PlayerWin => stake(hand),
+        Outcome::DealerWin => -stake(hand),
+        Outcome::Push => 0.0,
+        Outcome::Surrender => -0.5,
+    };
+    Some(net)
+}
+
+/// Insurance costs half the bet and pays 2:1 when the dealer has blackjack, as (stake, net).
+fn insurance(seat: &Seat, round: &Round) -> (f64, f64) {
+    if !seat.is_insured() {
+        (0.0, 0.0)
+    } else if round.dealer().is_blackjack() {
+        (0.5, 1.0)
+    } else {
+        (0.5, -0.5)
+    }
+}
+
+/// A player's money across rounds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Bankroll {
+    balance: f64,
+}
+
+impl Bankroll {
+    pub fn new(balance: f64) -> Result<Self, BetError> {
+        if balance.is_finite() {
+            Ok(Bankroll { balance })
+        } else {
+            Err(BetError::InvalidBankroll(balance))
+        }
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
+
+    pub fn apply(&mut self, settlement: &Settlement) {
+        self.balance += settlement.net;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Deck;
+    use crate::card::parse_cards;
+    use crate::deck_generator::complete_deck;
+    use crate::game::{Action, Game};
+    use crate::rules::Payout;
+
+    fn deck(cards: &str) -> Deck {
+        complete_deck(parse_cards(cards).unwrap(), 1).unwrap()
+    }
+
+    fn ten() -> Bet {
+        Bet::new(10.0).unwrap()
+    }
+
+    fn play(cards: &str, rules: Rules, actions: &[Action]) -> Game {
+        let mut game = Game::new(deck(cards), rules, 1).unwrap();
+        for action in actions {
+            game.act(*action).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn blackjack_pays_the_table_payout() {
+        let game = play("SA,HK,C10,D7", Rules::casino(), &[]);
+        assert_eq!(
+            settle(game.round(), 0, ten()),
+            Some(Settlement {
+                wagered: 10.0,
+                net: 15.0
+            })
+        );
+
+        let six_to_five = Rules {
+            blackjack_payout: Payout { pays: 6, to: 5 },
+            ..Rules::casino()
+        };
+        let game = play("SA,HK,C10,D7", six_to_five, &[]);
+        assert_eq!(settle(game.round(), 0, ten()).unwrap().net, 12.0);
+    }
+
+    #[test]
+    fn doubles_and_splits_settle_every_stake() {
+        let game = play(
+            "S8,H8,C10,D7,S3,DK,C2",
+            Rules::casino(),
+            &[Action::Split, Action::Double, Action::Stand],
+        );
+        assert_eq!(
+            settle(game.round(), 0, ten()),
+            Some(Settlement {
+                wagered: 30.0,
+                net: 10.0
+            })
+        );
+    }
+
+    #[test]
+    fn surrender_loses_half_the_bet() {
+        let game = play("SK,H6,C10,D6", Rules::casino(), &[Action::Surrender]);
+        assert_eq!(settle(game.round(), 0, ten()).unwrap().net, -5.0);
+    }
+
+    #[test]
+    fn insurance_pays_two_to_one_and_even_money_pays_one_to_one() {
+        let mut insured = Game::new(deck("SK,H9,CA,DK"), Rules::casino(), 1).unwrap();
+        insured.insure(true).unwrap();
+        assert_eq!(
+            settle(insured.round(), 0, ten()),
+            Some(Settlement {
+                wagered: 15.0,
+                net: 0.0
+            })
+        );
+
+        let mut even_money = Game::new(deck("SA,HK,CA,D7"), Rules::casino(), 1).unwrap();
+        even_money.insure(true).unwrap();
+        assert_eq!(settle(even_money.round(), 0, ten()).unwrap().net, 10.0);
+    }
+
+    #[test]
+    fn unsettled_rounds_have_no_settlement() {
+        let game = Game::new(deck("SK,H6,C10,D6"), Rules::casino(), 1).unwrap();
+        assert_eq!(settle(game.round(), 0, ten()), None);
+    }
+
+    #[test]
+    fn bankrolls_add_up_settlements() {
+        let mut bankroll = Bankroll::new(100.0).unwrap();
+        bankroll.apply(&Settlement {
+            wagered: 10.0,
+            net: -10.0,
+        });
+        bankroll.apply(&Settlement {
+            wagered: 10.0,
+            net: 15.0,
+        });
+        assert_eq!(bankroll.balance(), 105.0);
+    }
+
+    #[test]
+    fn only_positive_finite_amounts_are_bet() {
+        assert_eq!(Bet::new(10.0).unwrap().amount(), 10.0);
+        for amount in [0.0, -10.0, f64::NAN, f64::INFINITY] {
+            assert!(Bet::new(amount).is_err());
+        }
+        assert_eq!(Bet::units(0).amount(), 1.0);
+        assert!(Bankroll::new(f64::NAN).is_err());
+        assert_eq!(Bankroll::new(-5.0).unwrap().balance(), -5.0);
+    }
+}
@@ -1,8 +1,11 @@
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 
 use serde::{Deserialize, Serialize};
 use strum_macros::{EnumIter, EnumString};
 
+use crate::error::CardParseError;
+
 #[derive(Debug, Serialize, Deserialize, Clone, EnumIter, EnumString, Eq, PartialEq, Hash)]
 pub enum Suit {
     #[serde(rename = "SPADES")]
@@ -45,6 +48,24 @@ pub enum Rank {
     Ace,
 }
 
+impl Rank {
+    /// What the rank adds to a hand, counting an ace as 1.
+    pub fn points(&self) -> u8 {
+        match self {
+            Rank::Two => 2,
+            Rank::Three => 3,
+            Rank::Four => 4,
+            Rank::Five => 5,
+            Rank::Six => 6,
+            Rank::Seven => 7,
+            Rank::Eight => 8,
+            Rank::Nine => 9,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => 10,
+            Rank::Ace => 1,
+        }
+    }
+}
+
 impl Ord for Card {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.standard_index().cmp(&other.standard_index())
@@ -63,6 +84,34 @@ pub struct Card {
     pub value: Rank,
 }
 
+impl Display for Card {
+    /// Writes the card in the answer notation, e.g. `SA` or `H10`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suit = match self.suit {
+            Suit::Spades => 'S',
+            Suit::Hearts => 'H',
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+        };
+        let rank = match self.value {
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Ace => "A",
+        };
+        write!(f, "{}{}", suit, rank)
+    }
+}
+
 impl Card {
     pub fn standard_index(&self) -> usize {
         ((self.suit.clone() as u8) * 13 + self.value.clone() as u8) as usize
@@ -74,45 +123,91 @@ pub trait FromAnswer: Sized {
 }
 
 impl FromAnswer for Card {
-    type Err = ();
+    type Err = CardParseError;
 
     fn from_answer(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        if let Some(suit) = {
-            match chars.next() {
-                Some('S') => Some(Suit::Spades),
-                Some('D') => Some(Suit::Diamonds),
-                Some('H') => Some(Suit::Hearts),
-                Some('C') => Some(Suit::Clubs),
-                _ => None,
-            }
-        } {
-            if let Some(rank) = {
-                match chars.next() {
-                    Some('2') => Some(Rank::Two),
-                    Some('3') => Some(Rank::Three),
-                    Some('4') => Some(Rank::Four),
-                    Some('5') => Some(Rank::Five),
-                    Some('6') => Some(Rank::Six),
-                    Some('7') => Some(Rank::Seven),
-                    Some('8') => Some(Rank::Eight),
-                    Some('9') => Some(Rank::Nine),
-                    Some('1') => Some(Rank::Ten),
-                    Some('J') => Some(Rank::Jack),
-                    Some('Q') => Some(Rank::Queen),
-                    Some('K') => Some(Rank::King),
-                    Some('A') => Some(Rank::Ace),
-                    _ => None,
-                }
-            } {
-                Result::Ok(Card { suit, value: rank })
-            } else {
-                Result::Err(())
+        parse_token(s, 1)
+    }
+}
+
+/// Parses a comma separated list of cards in the answer notation, e.g. `SA,SK,H10`.
+/// Fails with an error for every invalid token, each with the 1-based position of the token.
+/// An empty string is an empty list.
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, Vec<CardParseError>> {
+    if s.is_empty() {
+        return Ok(vec![]);
+    }
+    let (cards, errors): (Vec<_>, Vec<_>) = s
+        .split(',')
+        .enumerate()
+        .map(|(index, token)| parse_token(token, index + 1))
+        .partition(Result::is_ok);
+    if errors.is_empty() {
+        Ok(cards.into_iter().flatten().collect())
+    } else {
+        Err(errors.into_iter().filter_map(Result::err).collect())
+    }
+}
+
+fn parse_token(token: &str, position: usize) -> Result<Card, CardParseError> {
+    let mut chars = token.chars();
+    let suit = match chars.next() {
+        Some('S') => Suit::Spades,
+        Some('D') => Suit::Diamonds,
+        Some('H') => Suit::Hearts,
+        Some('C') => Suit::Clubs,
+        Some(suit) => {
+            return Err(CardParseError::UnknownSuit {
+                position,
+                token: token.into(),
+                suit,
+            });
+        }
+        None => return Err(CardParseError::Empty { position }),
+    };
+    let rank = match chars.next() {
+        Some('2') => Rank::Two,
+        Some('3') => Rank::Three,
+        Some('4') => Rank::Four,
+        Some('5') => Rank::Five,
+        Some('6') => Rank::Six,
+        Some('7') => Rank::Seven,
+        Some('8') => Rank::Eight,
+        Some('9') => Rank::Nine,
+        Some('1') => {
+            // Tens are written as both S1 and S10
+            if chars.as_str().starts_with('0') {
+                chars.next();
             }
-        } else {
-            Result::Err(())
+            Rank::Ten
+        }
+        Some('J') => Rank::Jack,
+        Some('Q') => Rank::Queen,
+        Some('K') => Rank::King,
+        Some('A') => Rank::Ace,
+        Some(rank) => {
+            return Err(CardParseError::UnknownRank {
+                position,
+                token: token.into(),
+                rank,
+            });
+        }
+        None => {
+            return Err(CardParseError::MissingRank {
+                position,
+                token: token.into(),
+            });
         }
+    };
+    let trailing = chars.as_str();
+    if !trailing.is_empty() {
+        return Err(CardParseError::TrailingCharacters {
+            position,
+            token: token.into(),
+            trailing: trailing.into(),
+        });
     }
+    Ok(Card { suit, value: rank })
 }
 
 #[cfg(test)]
@@ -137,4 +232,67 @@ mod tests {
         assert_eq!(drawn_card, card);
         assert_ne!(drawn_card, dummy);
     }
+
+    #[test]
+    fn parses_tens_written_with_and_without_the_zero() {
+        let ten = Card {
+            suit: Suit::Hearts,
+            value: Rank::Ten,
+        };
+        assert_eq!(Card::from_answer("H1"), Ok(ten.clone()));
+        assert_eq!(Card::from_answer("H10"), Ok(ten));
+    }
+
+    #[test]
+    fn parse_cards_reports_every_wrong_token() {
+        assert_eq!(
+            parse_cards("SA,SK,SX"),
+            Err(vec![CardParseError::UnknownRank {
+                position: 3,
+                token: "SX".into(),
+                rank: 'X',
+            }])
+        );
+        assert_eq!(
+            parse_cards("SA,XK,,S,SAK"),
+            Err(vec![
+                CardParseError::UnknownSuit {
+                    position: 2,
+                    token: "XK".into(),
+                    suit: 'X',
+                },
+                CardParseError::Empty { position: 3 },
+                CardParseError::MissingRank {
+                    position: 4,
+                    token: "S".into(),
+                },
+                CardParseError::TrailingCharacters {
+                    position: 5,
+                    token: "SAK".into(),
+                    trailing: "K".into(),
+                },
+            ])
+        );
+        assert_eq!(
+            CardParseError::UnknownSuit {
+                position: 2,
+                token: "XK".into(),
+                suit: 'X',
+            }
+            .to_string(),
+            "token 2 'XK': unknown suit 'X'"
+        );
+    }
+
+    #[test]
+    fn cards_display_in_answer_notation() {
+        for answer in ["SA", "H10", "CK", "D2"] {
+            assert_eq!(Card::from_answer(answer).unwrap().to_string(), answer);
+        }
+    }
+
+    #[test]
+    fn parse_cards_accepts_an_empty_list() {
+        assert_eq!(parse_cards(""), Ok(vec![]));
+    }
 }
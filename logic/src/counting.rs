@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, Rank};
+use crate::deck_generator::DECK;
+use crate::game::Round;
+
+/// The KO running count at which the player's edge is about what a Hi-Lo true count of the
+/// same value gives, however many decks are left. KO is unbalanced so that its running count
+/// can be bet off as it is, without working out a true count.
+pub const KO_PIVOT: i32 = 4;
+
+/// A card counting system, which tags every card with a value to add to the count.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum CountingSystem {
+    /// 2-6 count +1, 7-9 count 0 and tens and aces count -1.
+    HiLo,
+    /// Knock-Out: like Hi-Lo, but sevens count +1 too, so the count isn't balanced and
+    /// starts below zero to end on +4 after a whole shoe.
+    Ko,
+    /// 2, 3 and 7 count +1, 4-6 count +2, 9 counts -1, tens count -2, 8 and aces count 0.
+    OmegaII,
+}
+
+impl CountingSystem {
+    pub fn tag(&self, card: &Card) -> i32 {
+        match self {
+            CountingSystem::HiLo => match card.value.points() {
+                2..=6 => 1,
+                7..=9 => 0,
+                _ => -1,
+            },
+            CountingSystem::Ko => match card.value.points() {
+                2..=7 => 1,
+                8 | 9 => 0,
+                _ => -1,
+            },
+            CountingSystem::OmegaII => match card.value {
+                Rank::Two | Rank::Three | Rank::Seven => 1,
+                Rank::Four | Rank::Five | Rank::Six => 2,
+                Rank::Eight | Rank::Ace => 0,
+                Rank::Nine => -1,
+                Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -2,
+            },
+        }
+    }
+
+    /// The running count before the first card of a fresh `decks` deck shoe.
+    pub fn initial_count(&self, decks: usize) -> i32 {
+        match self {
+            CountingSystem::Ko => 4 - 4 * decks as i32,
+            CountingSystem::HiLo | CountingSystem::OmegaII => 0,
+        }
+    }
+}
+
+/// Keeps the count for a shoe of `decks` decks as its cards are seen.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Counter {
+    system: CountingSystem,
+    decks: usize,
+    running: i32,
+    seen: usize,
+}
+
+impl Counter {
+    pub fn new(system: CountingSystem, decks: usize) -> Self {
+        Counter {
+            system,
+            decks,
+            running: system.initial_count(decks),
+            seen: 0,
+        }
+    }
+
+    pub fn system(&self) -> CountingSystem {
+        self.system
+    }
+
+    pub fn see(&mut self, card: &Card) {
+        self.running += self.system.tag(card);
+        self.seen += 1;
+    }
+
+    pub fn see_all<'a>(&mut self, cards: impl IntoIterator<Item = &'a Card>) {
+        for card in cards {
+            self.see(card);
+        }
+    }
+
+    /// Sees every card dealt in `round`, once it is settled and the hole card is turned.
+    pub fn see_round(&mut self, round: &Round) {
+        let player_cards = round
+            .seats()
+            .iter()
+            .flat_map(|seat| seat.hands())
+            .flat_map(|hand| hand.hand().cards());
+        self.see_all(player_cards.chain(round.dealer().cards()));
+    }
+
+    pub fn running(&self) -> i32 {
+        self.running
+    }
+
+    /// Decks left in the shoe, going by the cards seen so far.
+    pub fn decks_remaining(&self) -> f64 {
+        let left = (self.decks * DECK.len()).saturating_sub(self.seen);
+        left as f64 / DECK.len() as f64
+    }
+
+    /// The running count per deck left in the shoe. With less than half a deck left, the
+    /// count is divided by half a deck, so the last few cards don't blow it up.
+    pub fn true_count(&self) -> f64 {
+        f64::from(self.running) / self.decks_remaining().max(0.5)
+    }
+
+    /// The count to size bets by, on the scale of a true count: the true count for the
+    /// balanced systems, and the running count itself for KO, see [`KO_PIVOT`].
+    pub fn betting_count(&self) -> f64 {
+        match self.system {
+            CountingSystem::Ko => f64::from(self.running),
+            CountingSystem::HiLo | CountingSystem::OmegaII => self.true_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_cards;
+    use crate::deck_generator::shoe;
+
+    #[test]
+    fn balanced_systems_count_a_whole_shoe_to_zero() {
+        for system in [CountingSystem::HiLo, CountingSystem::OmegaII] {
+            let mut counter = Counter::new(system, 6);
+            counter.see_all(shoe(6).cards());
+            assert_eq!(counter.running(), 0);
+            assert_eq!(counter.decks_remaining(), 0.0);
+        }
+
+        let mut ko = Counter::new(CountingSystem::Ko, 6);
+        assert_eq!(ko.running(), -20);
+        ko.see_all(shoe(6).cards());
+        assert_eq!(ko.running(), 4);
+    }
+
+    #[test]
+    fn cards_are_tagged_per_system() {
+        let cards = parse_cards("S2,H5,C7,D9,SK,HA").unwrap();
+        let count = |system| {
+            let mut counter = Counter::new(system, 1);
+            counter.see_all(&cards);
+            counter.running()
+        };
+        assert_eq!(count(CountingSystem::HiLo), 0);
+        assert_eq!(count(CountingSystem::Ko), 1);
+        assert_eq!(count(CountingSystem::OmegaII), 1);
+    }
+
+    #[test]
+    fn the_true_count_divides_by_the_decks_left() {
+        let mut counter = Counter::new(CountingSystem::HiLo, 2);
+        counter.see_all(&parse_cards("S2,S3,S4,S5,S6,H2,H3,H4,H5,H6,C2,C3,C4").unwrap());
+        assert_eq!(counter.running(), 13);
+        assert_eq!(counter.decks_remaining(), 1.75);
+        assert!((counter.true_count() - 13.0 / 1.75).abs() < 1e-12);
+        assert_eq!(counter.betting_count(), counter.true_count());
+    }
+
+    #[test]
+    fn ko_bets_off_its_running_count() {
+        // Six decks start at -20, so 24 low cards bring the count up to the pivot.
+        let mut counter = Counter::new(CountingSystem::Ko, 6);
+        let low = shoe(6)
+            .cards()
+            .iter()
+            .filter(|card| CountingSystem::Ko.tag(card) == 1)
+            .take(24)
+            .cloned()
+            .collect::<Vec<_>>();
+        counter.see_all(&low);
+        assert_eq!(counter.running(), KO_PIVOT);
+        assert_eq!(counter.betting_count(), f64::from(KO_PIVOT));
+
+        counter.see_all(&parse_cards("SK,HK").unwrap());
+        assert_eq!(counter.running(), KO_PIVOT - 2);
+        assert_eq!(counter.betting_count(), f64::from(KO_PIVOT - 2));
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{AsRefStr, EnumString};
+
+use crate::card::{Card, parse_cards};
+use crate::deck_generator::standard_order;
+use crate::error::{DealingOrderError, RoleError};
+
+/// The most cards burnt before the deal, more than any table burns.
+pub const MAX_BURN: usize = 5;
+
+/// How the starting hands go round the table.
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, EnumString, AsRefStr, Eq, PartialEq, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DealStyle {
+    /// Two cards to each player in turn, then two to the dealer, like the interview game.
+    #[default]
+    Paired,
+    /// One card to each player and then the dealer, twice round, like a casino.
+    Alternating,
+}
+
+/// The order the cards of the deal come off the deck: `burn` cards are burnt first, then the
+/// starting hands are dealt in `style`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct DealingOrder {
+    pub style: DealStyle,
+    pub burn: usize,
+}
+
+/// Who takes a card of the deal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Receiver {
+    Burn,
+    Seat(usize),
+    Dealer,
+}
+
+impl DealingOrder {
+    /// Reads `?order=&burn=`, dealing paired without burning a card when neither is given.
+    pub fn parse(order: Option<&str>, burn: Option<usize>) -> Result<Self, DealingOrderError> {
+        let style = match order {
+            None => DealStyle::default(),
+            Some(order) => order
+                .parse()
+                .map_err(|_| DealingOrderError::UnknownStyle(order.to_string()))?,
+        };
+        let burn = burn.unwrap_or_default();
+        if burn > MAX_BURN {
+            return Err(DealingOrderError::TooManyBurnt(burn));
+        }
+        Ok(DealingOrder { style, burn })
+    }
+
+    /// How many cards the deal takes at a table of `seats` players, the burnt ones included.
+    pub fn deal_len(&self, seats: usize) -> usize {
+        self.burn + 2 * (seats + 1)
+    }
+
+    /// Who takes card number `dealt`, counted from 0, of the deal at a table of `seats` players.
+    pub fn receiver(&self, dealt: usize, seats: usize) -> Receiver {
+        let Some(index) = dealt.checked_sub(self.burn) else {
+            return Receiver::Burn;
+        };
+        let hand = match self.style {
+            DealStyle::Paired => index / 2,
+            DealStyle::Alternating => index % (seats + 1),
+        };
+        if hand < seats {
+            Receiver::Seat(hand)
+        } else {
+            Receiver::Dealer
+        }
+    }
+}
+
+/// Who a card is meant for in a one seat round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The player's cards on the deal.
+    Player,
+    /// The dealer's cards on the deal, the first one face up.
+    Dealer,
+    PlayerHits,
+    DealerHits,
+}
+
+impl Display for Role {
+    /// Writes the role the way the query names it.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Role::Player => "player",
+            Role::Dealer => "dealer",
+            Role::PlayerHits => "player_hits",
+            Role::DealerHits => "dealer_hits",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The cards each role in a one seat round should get, in the order they get them.
+///
+/// The player and the dealer get at most two cards each on the deal. The dealer's hits come
+/// after every one of the player's, so they assume the player stands after the hits given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Roles {
+    pub player: Vec<Card>,
+    pub dealer: Vec<Card>,
+    pub player_hits: Vec<Card>,
+    pub dealer_hits: Vec<Card>,
+}
+
+impl Roles {
+    /// Parses each role's cards in the answer notation, collecting every invalid card and
+    /// every deal of more than two cards.
+    pub fn parse(
+        player: Option<&str>,
+        dealer: Option<&str>,
+        player_hits: Option<&str>,
+        dealer_hits: Option<&str>,
+    ) -> Result<Roles, Vec<RoleError>> {
+        let mut errors = vec![];
+        let mut parse = |role: Role, cards: Option<&str>| {
+            let cards = match parse_cards(cards.unwrap_or_default()) {
+                Ok(cards) => cards,
+                Err(invalid) => {
+                    errors.extend(
+                        invalid
+                            .into_iter()
+                            .map(|error| RoleError::InvalidCard { role, error }),
+                    );
+                    vec![]
+                }
+            };
+            if matches!(role, Role::Player | Role::Dealer) && cards.len() > 2 {
+                errors.push(RoleError::TooManyCards {
+                    role,
+                    cards: cards.len(),
+                });
+            }
+            cards
+        };
+        let roles = Roles {
+            player: parse(Role::Player, player),
+            dealer: parse(Role::Dealer, dealer),
+            player_hits: parse(Role::PlayerHits, player_hits),
+            dealer_hits: parse(Role::DealerHits, dealer_hits),
+        };
+        if errors.is_empty() {
+            Ok(roles)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The roles of `cards` dealt in the paired order. Every card after the deal counts as a
+    /// player hit, hits come off the deck in the same order whoever takes them.
+    pub fn from_paired(cards: &[Card]) -> Roles {
+        let (player, rest) = cards.split_at(cards.len().min(2));
+        let (dealer, hits) = rest.split_at(rest.len().min(2));
+        Roles {
+            player: player.to_vec(),
+            dealer: dealer.to_vec(),
+            player_hits: hits.to_vec(),
+            dealer_hits: vec![],
+        }
+    }
+
+    /// Every role's cards, each with its role and 1-based position within the role.
+    fn cards(&self) -> impl Iterator<Item = (Role, usize, &Card)> {
+        [
+            (Role::Player, &self.player),
+            (Role::Dealer, &self.dealer),
+            (Role::PlayerHits, &self.player_hits),
+            (Role::DealerHits, &self.dealer_hits),
+        ]
+        .into_iter()
+        .flat_map(|(role, cards)| {
+            cards
+                .iter()
+                .enumerate()
+                .map(move |(index, card)| (role, index + 1, card))
+        })
+    }
+
+    /// The top of a `decks` deck shoe that deals every role its cards in `order`: the deal,
+    /// then the hits. A burnt card, or a deal card that isn't given, is filled in with the
+    /// first card of the shoe no role asked for when given cards come after it.
+    ///
+    /// Fails when a card is given to more roles than a shoe of `decks` decks has copies of it,
+    /// or when the shoe runs out of cards to fill the deal with.
+    pub fn front(&self, order: DealingOrder, decks: usize) -> Result<Vec<Card>, Vec<RoleError>> {
+        let mut copies = [0usize; 52];
+        let mut taken_by = [None; 52];
+        let mut conflicts = vec![];
+        for (role, position, card) in self.cards() {
+            let index = card.standard_index();
+            if copies[index] == decks {
+                conflicts.push(RoleError::Conflict {
+                    role,
+                    position,
+                    card: card.clone(),
+                    taken_by: taken_by[index].expect("A copy was taken"),
+                });
+            } else {
+                copies[index] += 1;
+                taken_by[index] = Some(role);
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+
+        let mut unused = standard_order(decks).into_iter().filter(|card| {
+            let copies = &mut copies[card.standard_index()];
+            if *copies > 0 {
+                *copies -= 1;
+                false
+            } else {
+                true
+            }
+        });
+        let (mut player, mut dealer) = (self.player.iter(), self.dealer.iter());
+        let mut slots: Vec<Option<Card>> = (0..order.deal_len(1))
+            .map(|dealt| match order.receiver(dealt, 1) {
+                Receiver::Burn => None,
+                Receiver::Seat(_) => player.next().cloned(),
+                Receiver::Dealer => dealer.next().cloned(),
+            })
+            .collect();
+        slots.extend(
+            self.player_hits
+                .iter()
+                .chain(&self.dealer_hits)
+                .cloned()
+                .map(Some),
+        );
+        while let Some(None) = slots.last() {
+            slots.pop();
+        }
+        let empty = slots.iter().filter(|slot| slot.is_none()).count();
+        let left = 52 * decks - self.cards().count();
+        if empty > left {
+            return Err(vec![RoleError::TooManyForDeal {
+                cards: slots.len(),
+                shoe: 52 * decks,
+            }]);
+        }
+        Ok(slots
+            .into_iter()
+            .map(|slot| slot.unwrap_or_else(|| unused.next().expect("Shoe has cards left")))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        parse_cards(s).unwrap()
+    }
+
+    #[test]
+    fn roles_are_dealt_player_player_dealer_dealer_then_hits() {
+        let roles = Roles::parse(Some("S5,H2"), Some("D5,C9"), Some("S6,D4"), Some("S8")).unwrap();
+        assert_eq!(
+            roles.front(DealingOrder::default(), 1).unwrap(),
+            cards("S5,H2,D5,C9,S6,D4,S8")
+        );
+    }
+
+    #[test]
+    fn missing_deal_cards_are_filled_from_the_shoe() {
+        let roles = Roles::parse(Some("S2"), None, Some("SK"), None).unwrap();
+        assert_eq!(
+            roles.front(DealingOrder::default(), 1).unwrap(),
+            cards("S2,S3,S4,S5,SK")
+        );
+
+        let dealer_only = Roles::parse(None, Some("HA"), None, None).unwrap();
+        assert_eq!(
+            dealer_only.front(DealingOrder::default(), 1).unwrap(),
+            cards("S2,S3,HA")
+        );
+    }
+
+    #[test]
+    fn the_deal_goes_round_the_table_in_order() {
+        let alternating = DealingOrder::parse(Some("alternating"), None).unwrap();
+        let receivers: Vec<Receiver> = (0..6).map(|dealt| alternating.receiver(dealt, 2)).collect();
+        assert_eq!(
+            receivers,
+            [
+                Receiver::Seat(0),
+                Receiver::Seat(1),
+                Receiver::Dealer,
+                Receiver::Seat(0),
+                Receiver::Seat(1),
+                Receiver::Dealer
+            ]
+        );
+
+        let paired = DealingOrder::parse(None, Some(1)).unwrap();
+        let receivers: Vec<Receiver> = (0..5).map(|dealt| paired.receiver(dealt, 1)).collect();
+        assert_eq!(
+            receivers,
+            [
+                Receiver::Burn,
+                Receiver::Seat(0),
+                Receiver::Seat(0),
+                Receiver::Dealer,
+                Receiver::Dealer
+            ]
+        );
+        assert_eq!(paired.deal_len(1), 5);
+    }
+
+    #[test]
+    fn roles_follow_the_dealing_order() {
+        let roles = Roles::parse(Some("S5,H2"), Some("D5,C9"), Some("S6"), None).unwrap();
+        let casino = DealingOrder {
+            style: DealStyle::Alternating,
+            burn: 1,
+        };
+        assert_eq!(roles.front(casino, 1).unwrap(), cards("S2,S5,D5,H2,C9,S6"));
+
+        let blackjack = cards("SA,SJ");
+        let roles = Roles::from_paired(&blackjack);
+        assert_eq!(roles.front(DealingOrder::default(), 1).unwrap(), blackjack);
+        assert_eq!(roles.front(casino, 1).unwrap(), cards("S2,SA,S3,SJ"));
+    }
+
+    #[test]
+    fn unknown_orders_and_big_burns_are_rejected() {
+        assert_eq!(
+            DealingOrder::parse(Some("casino"), None),
+            Err(DealingOrderError::UnknownStyle("casino".into()))
+        );
+        assert_eq!(
+            DealingOrder::parse(None, Some(MAX_BURN + 1)),
+            Err(DealingOrderError::TooManyBurnt(MAX_BURN + 1))
+        );
+    }
+
+    #[test]
+    fn a_card_given_to_two_roles_is_a_conflict() {
+        let roles = Roles::parse(Some("SA,SK"), Some("SA"), None, None).unwrap();
+        assert_eq!(
+            roles.front(DealingOrder::default(), 1),
+            Err(vec![RoleError::Conflict {
+                role: Role::Dealer,
+                position: 1,
+                card: cards("SA")[0].clone(),
+                taken_by: Role::Player,
+            }])
+        );
+        assert!(roles.front(DealingOrder::default(), 2).is_ok());
+    }
+
+    #[test]
+    fn a_deal_longer_than_the_shoe_is_refused() {
+        let whole_deck: Vec<String> = standard_order(1).iter().map(Card::to_string).collect();
+        let roles = Roles::parse(None, None, Some(&whole_deck.join(",")), None).unwrap();
+        assert_eq!(
+            roles.front(DealingOrder::default(), 1),
+            Err(vec![RoleError::TooManyForDeal {
+                cards: 56,
+                shoe: 52
+            }])
+        );
+        assert_eq!(roles.front(DealingOrder::default(), 2).unwrap().len(), 56);
+    }
+
+    #[test]
+    fn every_bad_role_is_reported() {
+        let errors = Roles::parse(Some("SA,SK,SQ"), Some("ZK"), None, None).unwrap_err();
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "player: 3 cards, only 2 are dealt, put the rest in player_hits",
+                "dealer: token 1 'ZK': unknown suit 'Z'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::iter::repeat_n;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::card::Card;
+use crate::deck_generator::{DECK, DECKSET, MAX_DECKS};
+use crate::error::DeckError;
+
+/// A deck, or a shoe of several decks, that is always a permutation of whole decks.
+///
+/// Drawing does not shrink the deck, it moves past the top card, so `size` stays the size of
+/// the shoe while `remaining` counts the cards left to draw. Serializes as the JSON array of the
+/// cards left to draw, which for a fresh deck is the full deck, and deserializes from one with
+/// [`Deck::from_remaining`], so a deck that was drawn from reads back with the same cards left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    cards: Vec<Card>,
+    decks: usize,
+    top: usize,
+}
+
+impl Deck {
+    /// Checks that `cards` holds every card exactly once per deck, for 1 to `MAX_DECKS` decks.
+    pub fn new(cards: Vec<Card>) -> Result<Self, DeckError> {
+        let decks = cards.len() / DECKSET.len();
+        if cards.len() % DECKSET.len() != 0 || !(1..=MAX_DECKS).contains(&decks) {
+            return Err(DeckError::InvalidSize(cards.len()));
+        }
+
+        let mut copies: HashMap<&Card, usize> = HashMap::new();
+        for card in &cards {
+            *copies.entry(card).or_default() += 1;
+        }
+        let mut missing: Vec<Card> = DECKSET
+            .iter()
+            .filter(|card| copies.get(card).copied().unwrap_or_default() < decks)
+            .cloned()
+            .collect();
+        let mut duplicated: Vec<Card> = copies
+            .iter()
+            .filter(|(_, count)| **count > decks)
+            .map(|(card, _)| (*card).clone())
+            .collect();
+        if !missing.is_empty() || !duplicated.is_empty() {
+            missing.sort();
+            duplicated.sort();
+            return Err(DeckError::WrongComposition {
+                decks,
+                missing,
+                duplicated,
+            });
+        }
+
+        Ok(Self::new_unchecked(cards, decks))
+    }
+
+    /// The deck `cards` are what is left of: the fewest whole decks that hold them, with the
+    /// cards missing from `cards` counted as drawn already. Which cards were drawn, and in what
+    /// order, can't be told from what is left, so they are taken to be in standard order.
+    pub fn from_remaining(cards: Vec<Card>) -> Result<Self, DeckError> {
+        let mut copies = [0usize; 52];
+        for card in &cards {
+            copies[card.standard_index()] += 1;
+        }
+        let decks = copies
+            .iter()
+            .copied()
+            .chain([cards.len().div_ceil(DECK.len()), 1])
+            .max()
+            .unwrap_or(1);
+        if decks > MAX_DECKS {
+            return Err(DeckError::InvalidDeckCount(decks));
+        }
+
+        let mut all: Vec<Card> = DECK
+            .iter()
+            .flat_map(|card| repeat_n(card.clone(), decks - copies[card.standard_index()]))
+            .collect();
+        let top = all.len();
+        all.extend(cards);
+        Ok(Deck {
+            cards: all,
+            decks,
+            top,
+        })
+    }
+
+    /// For the generators, which build decks that are complete by construction.
+    pub(crate) fn new_unchecked(cards: Vec<Card>, decks: usize) -> Self {
+        Deck {
+            cards,
+            decks,
+            top: 0,
+        }
+    }
+
+    /// Takes the top card off the deck.
+    pub fn draw(&mut self) -> Option<Card> {
+        let card = self.cards.get(self.top).cloned();
+        if card.is_some() {
+            self.top += 1;
+        }
+        card
+    }
+
+    /// The card `draw` would return next.
+    pub fn peek(&self) -> Option<&Card> {
+        self.cards.get(self.top)
+    }
+
+    /// Discards the top card, like a dealer burning the first card of a fresh shoe.
+    pub fn burn(&mut self) -> Option<Card> {
+        self.draw()
+    }
+
+    /// Moves the top `at` cards left to draw to the bottom of the deck.
+    pub fn cut(&mut self, at: usize) {
+        let remaining = &mut self.cards[self.top..];
+        let at = at.min(remaining.len());
+        remaining.rotate_left(at);
+    }
+
+    /// The cards left to draw, top card first.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards[self.top..]
+    }
+
+    /// Number of cards left to draw.
+    pub fn remaining(&self) -> usize {
+        self.cards.len() - self.top
+    }
+
+    /// Number of cards in the whole deck, including the ones already drawn.
+    pub fn size(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Whether every card has been drawn.
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Number of decks shuffled together in this shoe.
+    pub fn decks(&self) -> usize {
+        self.decks
+    }
+}
+
+impl TryFrom<Vec<Card>> for Deck {
+    type Error = DeckError;
+
+    fn try_from(cards: Vec<Card>) -> Result<Self, Self::Error> {
+        Deck::new(cards)
+    }
+}
+
+impl Serialize for Deck {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards().serialize(serializer)
+    }
+}
+
+/// The one way to build a deck that doesn't check for whole decks: any list of cards reads
+/// back as what is left of a shoe, with as many decks as the most copies of one card, up to
+/// `MAX_DECKS`. The cards it is short of count as drawn. `Deck::try_from` a `Vec<Card>` is
+/// the strict way, which only takes whole decks.
+impl<'de> Deserialize<'de> for Deck {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cards = Vec::<Card>::deserialize(deserializer)?;
+        Deck::from_remaining(cards).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::FromAnswer;
+    use crate::deck_generator::{DECK, shoe};
+
+    fn card(answer: &str) -> Card {
+        Card::from_answer(answer).unwrap()
+    }
+
+    #[test]
+    fn new_accepts_a_shuffled_shoe() {
+        let mut cards = shoe(2).cards().to_vec();
+        cards.reverse();
+
+        let deck = Deck::new(cards).unwrap();
+        assert_eq!(deck.decks(), 2);
+        assert_eq!(deck.size(), 104);
+    }
+
+    #[test]
+    fn new_rejects_duplicates_and_missing_cards() {
+        let mut cards = DECK.clone();
+        cards[1] = card("SA");
+
+        assert_eq!(
+            Deck::new(cards),
+            Err(DeckError::WrongComposition {
+                decks: 1,
+                missing: vec![card("S3")],
+                duplicated: vec![card("SA")],
+            })
+        );
+    }
+
+    #[test]
+    fn new_rejects_partial_decks() {
+        assert_eq!(
+            Deck::new(DECK[1..].to_vec()),
+            Err(DeckError::InvalidSize(51))
+        );
+        assert_eq!(Deck::new(vec![]), Err(DeckError::InvalidSize(0)));
+    }
+
+    #[test]
+    fn draw_peek_and_burn_move_through_the_deck() {
+        let mut deck = shoe(1);
+        assert_eq!(deck.burn(), Some(card("S2")));
+        assert_eq!(deck.peek(), Some(&card("S3")));
+        assert_eq!(deck.draw(), Some(card("S3")));
+        assert_eq!(deck.remaining(), 50);
+        assert_eq!(deck.size(), 52);
+
+        while deck.draw().is_some() {}
+        assert!(deck.is_empty());
+        assert_eq!(deck.peek(), None);
+    }
+
+    #[test]
+    fn cut_moves_the_top_cards_to_the_bottom() {
+        let mut deck = shoe(1);
+        deck.draw();
+        deck.cut(2);
+
+        assert_eq!(deck.peek(), Some(&card("S5")));
+        assert_eq!(deck.cards()[49..], [card("S3"), card("S4")]);
+    }
+
+    #[test]
+    fn serializes_like_a_list_of_cards() {
+        let deck = shoe(1);
+        assert_eq!(
+            serde_json::to_string(&deck).unwrap(),
+            serde_json::to_string(&*DECK).unwrap()
+        );
+
+        let round_trip: Deck =
+            serde_json::from_str(&serde_json::to_string(&deck).unwrap()).unwrap();
+        assert_eq!(round_trip, deck);
+    }
+
+    #[test]
+    fn a_deck_that_was_drawn_from_reads_back_with_the_same_cards_left() {
+        let mut deck = shoe(2);
+        for _ in 0..60 {
+            deck.draw();
+        }
+
+        let read: Deck = serde_json::from_str(&serde_json::to_string(&deck).unwrap()).unwrap();
+        assert_eq!(read.cards(), deck.cards());
+        assert_eq!(read.decks(), 1);
+        assert_eq!(read.size(), 52);
+        assert_eq!(read.remaining(), 44);
+    }
+
+    #[test]
+    fn the_cards_left_decide_how_many_decks_there_were() {
+        let aces = vec![card("SA"); 3];
+        let deck: Deck = serde_json::from_str(&serde_json::to_string(&aces).unwrap()).unwrap();
+        assert_eq!(deck.decks(), 3);
+        assert_eq!(deck.remaining(), 3);
+
+        let empty: Deck = serde_json::from_str("[]").unwrap();
+        assert_eq!(empty.decks(), 1);
+        assert!(empty.is_empty());
+
+        let too_many = serde_json::to_string(&vec![card("SA"); MAX_DECKS + 1]).unwrap();
+        assert!(serde_json::from_str::<Deck>(&too_many).is_err());
+    }
+}
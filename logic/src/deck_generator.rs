@@ -1,13 +1,16 @@
 use std::collections::HashSet;
 use std::iter::FromIterator;
+use std::num::ParseIntError;
 
 use lazy_static::lazy_static;
-use rand::rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng, rng};
 use strum::IntoEnumIterator;
 
+use crate::Deck;
 use crate::card::{Card, Rank, Suit};
+use crate::error::{DeckError, DuplicateCard};
 
 lazy_static! {
     pub static ref DECK: Vec<Card> = Suit::iter()
@@ -16,55 +19,119 @@ lazy_static! {
             suit: s.clone()
         }))
         .collect::<Vec<Card>>();
-    static ref DECKSET: HashSet<Card> = HashSet::from_iter(DECK.clone());
+    pub(crate) static ref DECKSET: HashSet<Card> = HashSet::from_iter(DECK.clone());
 }
 
-type Deck = Vec<Card>;
+/// The largest shoe we will build, 8 decks is the most you'll find on a real table.
+pub const MAX_DECKS: usize = 8;
+
+/// Checks a requested number of decks, defaulting to a single deck when none was asked for.
+pub fn deck_count(requested: Option<usize>) -> Result<usize, DeckError> {
+    match requested {
+        None => Ok(1),
+        Some(decks) if (1..=MAX_DECKS).contains(&decks) => Ok(decks),
+        Some(decks) => Err(DeckError::InvalidDeckCount(decks)),
+    }
+}
+
+/// `decks` copies of [`DECK`] one after the other, in standard order.
+pub fn shoe(decks: usize) -> Deck {
+    Deck::new_unchecked(standard_order(decks), decks)
+}
+
+pub(crate) fn standard_order(decks: usize) -> Vec<Card> {
+    DECK.iter()
+        .cloned()
+        .cycle()
+        .take(DECK.len() * decks)
+        .collect()
+}
 
 pub fn shuffle() -> Deck {
-    let mut rng = rng();
-    let mut deck_copy = DECK.clone();
-    deck_copy.shuffle(&mut rng);
-    deck_copy
+    shuffle_with(&mut rng(), 1)
 }
 
-//Clippy is wrong here, rustc requires the clone
-#[allow(clippy::redundant_clone)]
-pub fn complete_deck(front_of_deck: Vec<Card>) -> Deck {
-    let mut leaders = front_of_deck.clone();
-    leaders.sort();
-    let mut leader_iterator = leaders.iter();
-    let mut current_leader_index = leader_iterator.next().map(|card| card.standard_index());
-
-    let deck_without_leaders = DECK
-        .iter()
-        .enumerate()
-        .filter(|(index, _)| {
-            if current_leader_index == Some(*index) {
-                current_leader_index = leader_iterator.next().map(|card| card.standard_index());
-                false
-            } else {
-                true
-            }
-        })
-        .map(|(_, card)| card.clone());
+/// Shuffles a deck with an rng seeded from `seed`, so the same seed always yields the same deck.
+pub fn shuffle_seeded(seed: u64) -> Deck {
+    shuffled_shoe(1, seed)
+}
+
+/// Shuffles a shoe of `decks` decks together, reproducibly for the same seed and deck count.
+pub fn shuffled_shoe(decks: usize, seed: u64) -> Deck {
+    shuffle_with(&mut StdRng::seed_from_u64(seed), decks)
+}
+
+fn shuffle_with<R: Rng + ?Sized>(rng: &mut R, decks: usize) -> Deck {
+    let mut deck_copy = standard_order(decks);
+    deck_copy.shuffle(rng);
+    Deck::new_unchecked(deck_copy, decks)
+}
+
+pub fn random_seed() -> u64 {
+    rng().random()
+}
+
+/// Parses a seed given either as a decimal u64 or as hex with a `0x` prefix, so a seed like
+/// `10` is never read as hex.
+pub fn parse_seed(seed: &str) -> Result<u64, ParseIntError> {
+    match seed.strip_prefix("0x").or_else(|| seed.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => seed.parse::<u64>(),
+    }
+}
+
+/// Puts `front_of_deck` on top of a shoe of `decks` decks, followed by the rest of the shoe in
+/// standard order. Each card in the front takes the place of one of its copies in the shoe, so
+/// the front may not use a card more than `decks` times.
+pub fn complete_deck(front_of_deck: Vec<Card>, decks: usize) -> Result<Deck, DeckError> {
+    let mut to_skip = [0usize; 52];
+    let mut duplicates = vec![];
+    for (index, card) in front_of_deck.iter().enumerate() {
+        let copies = &mut to_skip[card.standard_index()];
+        if *copies == decks {
+            duplicates.push(DuplicateCard {
+                position: index + 1,
+                card: card.clone(),
+            });
+        } else {
+            *copies += 1;
+        }
+    }
+    if !duplicates.is_empty() {
+        return Err(DeckError::DuplicateCards { decks, duplicates });
+    }
+
+    let rest_of_shoe = standard_order(decks).into_iter().filter(|card| {
+        let skip = &mut to_skip[card.standard_index()];
+        if *skip > 0 {
+            *skip -= 1;
+            false
+        } else {
+            true
+        }
+    });
+
+    let mut deck = front_of_deck;
+    deck.extend(rest_of_shoe);
+    Ok(Deck::new_unchecked(deck, decks))
+}
 
-    let mut deck = front_of_deck.clone();
-    deck.extend(deck_without_leaders);
-    deck
+/// Completes one of the fixed scenarios below, which never reuse a card.
+fn complete_scenario(front_of_deck: Vec<Card>, decks: usize) -> Deck {
+    complete_deck(front_of_deck, decks).expect("Scenario uses a card twice")
 }
 
-pub fn four_aces() -> Deck {
+pub fn four_aces(decks: usize) -> Deck {
     let four_aces: Vec<Card> = Suit::iter()
         .map(|s| Card {
             suit: s,
             value: Rank::Ace,
         })
         .collect::<Vec<Card>>();
-    complete_deck(four_aces)
+    complete_scenario(four_aces, decks)
 }
 
-pub fn player_blackjack() -> Deck {
+pub fn player_blackjack(decks: usize) -> Deck {
     let player_blackjack = [
         Card {
             suit: Suit::Spades,
@@ -75,10 +142,10 @@ pub fn player_blackjack() -> Deck {
             value: Rank::Jack,
         },
     ];
-    complete_deck(player_blackjack.to_vec())
+    complete_scenario(player_blackjack.to_vec(), decks)
 }
 
-pub fn dealer_blackjack() -> Deck {
+pub fn dealer_blackjack(decks: usize) -> Deck {
     let dealer_blackjack = [
         Card {
             suit: Suit::Spades,
@@ -97,10 +164,10 @@ pub fn dealer_blackjack() -> Deck {
             value: Rank::Jack,
         },
     ];
-    complete_deck(dealer_blackjack.to_vec())
+    complete_scenario(dealer_blackjack.to_vec(), decks)
 }
 
-pub fn player_bust() -> Deck {
+pub fn player_bust(decks: usize) -> Deck {
     let player_bust = [
         Card {
             suit: Suit::Spades,
@@ -127,10 +194,10 @@ pub fn player_bust() -> Deck {
             value: Rank::King,
         },
     ];
-    complete_deck(player_bust.to_vec())
+    complete_scenario(player_bust.to_vec(), decks)
 }
 
-pub fn dealer_bust() -> Deck {
+pub fn dealer_bust(decks: usize) -> Deck {
     let dealer_bust = vec![
         Card {
             suit: Suit::Spades,
@@ -165,10 +232,10 @@ pub fn dealer_bust() -> Deck {
             value: Rank::King,
         },
     ];
-    complete_deck(dealer_bust)
+    complete_scenario(dealer_bust, decks)
 }
 
-pub fn both_blackjack() -> Deck {
+pub fn both_blackjack(decks: usize) -> Deck {
     let both_blackjack = vec![
         Card {
             suit: Suit::Spades,
@@ -187,10 +254,10 @@ pub fn both_blackjack() -> Deck {
             value: Rank::Ace,
         },
     ];
-    complete_deck(both_blackjack)
+    complete_scenario(both_blackjack, decks)
 }
 
-pub fn tie21() -> Deck {
+pub fn tie21(decks: usize) -> Deck {
     let tie21 = vec![
         Card {
             suit: Suit::Spades,
@@ -221,36 +288,31 @@ pub fn tie21() -> Deck {
             value: Rank::Nine,
         },
     ];
-    complete_deck(tie21)
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct BlackjackQuery {
-    pub cards: String,
+    complete_scenario(tie21, decks)
 }
 
 #[cfg(test)]
 mod blackjack {
     use super::*;
-    use crate::card::FromAnswer;
+    use crate::card::{FromAnswer, parse_cards};
 
     #[test]
     fn four_aces_returns_four_aces_as_first_four_cards() {
-        let f = four_aces();
+        let f = four_aces(1);
         let four_aces = [
             Card::from_answer("SA").unwrap(),
             Card::from_answer("HA").unwrap(),
             Card::from_answer("CA").unwrap(),
             Card::from_answer("DA").unwrap(),
         ];
-        assert_eq!(four_aces, f.chunks(4).next().unwrap())
+        assert_eq!(four_aces, f.cards()[0..4])
     }
 
     #[test]
     fn player_blackjack_deals_blackjack_to_player() {
-        let b = player_blackjack();
-        let first_player_card = b.first().unwrap();
-        let second_player_card = b.get(1).unwrap();
+        let b = player_blackjack(1);
+        let first_player_card = b.cards().first().unwrap();
+        let second_player_card = b.cards().get(1).unwrap();
 
         assert_eq!(first_player_card, &Card::from_answer("SA").unwrap());
         assert_eq!(second_player_card, &Card::from_answer("SJ").unwrap())
@@ -258,9 +320,9 @@ mod blackjack {
 
     #[test]
     fn dealer_blackjack_deals_a_blackjack_to_dealer() {
-        let b = dealer_blackjack();
-        let first_dealer_card = b.get(2).unwrap();
-        let second_dealer_card = b.get(3).unwrap();
+        let b = dealer_blackjack(1);
+        let first_dealer_card = b.cards().get(2).unwrap();
+        let second_dealer_card = b.cards().get(3).unwrap();
 
         assert_eq!(first_dealer_card, &Card::from_answer("SA").unwrap());
         assert_eq!(second_dealer_card, &Card::from_answer("SJ").unwrap())
@@ -275,9 +337,92 @@ mod blackjack {
             Card::from_answer("DA").unwrap(),
         ];
 
-        let new_deck = complete_deck(four_aces.to_vec());
-        let unique_cards: HashSet<Card> = HashSet::from_iter(new_deck.clone());
-        assert_eq!(new_deck.len(), 52);
+        let new_deck = complete_deck(four_aces.to_vec(), 1).unwrap();
+        let unique_cards: HashSet<Card> = HashSet::from_iter(new_deck.cards().to_vec());
+        assert_eq!(new_deck.size(), 52);
         assert_eq!(unique_cards.len(), 52);
     }
+
+    #[test]
+    fn complete_deck_with_a_shoe_keeps_every_copy_of_the_front_cards() {
+        let ace_of_spades = Card::from_answer("SA").unwrap();
+        let new_deck =
+            complete_deck(vec![ace_of_spades.clone(), ace_of_spades.clone()], 6).unwrap();
+
+        assert_eq!(new_deck.size(), 52 * 6);
+        assert_eq!(
+            new_deck.cards()[0..2],
+            [ace_of_spades.clone(), ace_of_spades.clone()]
+        );
+        assert_eq!(
+            new_deck
+                .cards()
+                .iter()
+                .filter(|c| **c == ace_of_spades)
+                .count(),
+            6
+        );
+        assert!(Deck::new(new_deck.cards().to_vec()).is_ok());
+    }
+
+    #[test]
+    fn complete_deck_rejects_more_copies_than_the_shoe_holds() {
+        let front = parse_cards("SA,HK,SA,HK,SA").unwrap();
+
+        assert_eq!(complete_deck(front.clone(), 3).unwrap().size(), 52 * 3);
+        assert_eq!(
+            complete_deck(front, 1),
+            Err(DeckError::DuplicateCards {
+                decks: 1,
+                duplicates: vec![
+                    DuplicateCard {
+                        position: 3,
+                        card: Card::from_answer("SA").unwrap(),
+                    },
+                    DuplicateCard {
+                        position: 4,
+                        card: Card::from_answer("HK").unwrap(),
+                    },
+                    DuplicateCard {
+                        position: 5,
+                        card: Card::from_answer("SA").unwrap(),
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn shuffled_shoe_contains_every_card_once_per_deck() {
+        let shuffled = shuffled_shoe(6, 1337);
+        assert_eq!(Deck::new(shuffled.cards().to_vec()).unwrap().decks(), 6);
+    }
+
+    #[test]
+    fn deck_count_defaults_to_one_and_rejects_out_of_range() {
+        assert_eq!(deck_count(None), Ok(1));
+        assert_eq!(deck_count(Some(6)), Ok(6));
+        assert_eq!(deck_count(Some(0)), Err(DeckError::InvalidDeckCount(0)));
+        assert_eq!(
+            deck_count(Some(MAX_DECKS + 1)),
+            Err(DeckError::InvalidDeckCount(MAX_DECKS + 1))
+        );
+    }
+
+    #[test]
+    fn shuffle_seeded_is_reproducible() {
+        assert_eq!(shuffle_seeded(1337), shuffle_seeded(1337));
+        assert_ne!(shuffle_seeded(1337), shuffle_seeded(1338));
+    }
+
+    #[test]
+    fn parse_seed_accepts_decimal_and_hex() {
+        assert_eq!(parse_seed("1337"), Ok(1337));
+        assert_eq!(parse_seed("0x539"), Ok(1337));
+        assert_eq!(parse_seed("0xdeadbeef"), Ok(0xdeadbeef));
+        assert_eq!(parse_seed("10"), Ok(10));
+        assert_eq!(parse_seed("0x10"), Ok(16));
+        assert!(parse_seed("deadbeef").is_err());
+        assert!(parse_seed("not a seed").is_err());
+    }
 }
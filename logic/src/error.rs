@@ -2,10 +2,16 @@ use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+use crate::card::Card;
+use crate::dealing::Role;
+use crate::game::{Action, Outcome};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ErrorMessage {
     pub code: u16,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
 }
 
 impl Display for ErrorMessage {
@@ -19,3 +25,541 @@ impl Error for ErrorMessage {
         &self.message
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeckError {
+    InvalidDeckCount(usize),
+    /// A deck must hold a whole number of decks.
+    InvalidSize(usize),
+    /// Some cards are not there exactly once per deck.
+    WrongComposition {
+        decks: usize,
+        missing: Vec<Card>,
+        duplicated: Vec<Card>,
+    },
+    /// The front of a deck holds more copies of some cards than a shoe of `decks` decks has.
+    DuplicateCards {
+        decks: usize,
+        duplicates: Vec<DuplicateCard>,
+    },
+}
+
+/// A card at `position` (1-based) that is one copy too many for the shoe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateCard {
+    pub position: usize,
+    pub card: Card,
+}
+
+impl Display for DuplicateCard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "token {} '{}': duplicate card", self.position, self.card)
+    }
+}
+
+impl Display for DeckError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeckError::InvalidDeckCount(decks) => write!(
+                f,
+                "Invalid number of decks {}, must be between 1 and {}",
+                decks,
+                crate::deck_generator::MAX_DECKS
+            ),
+            DeckError::InvalidSize(size) => write!(
+                f,
+                "A deck of {} cards is not made of 1 to {} whole decks",
+                size,
+                crate::deck_generator::MAX_DECKS
+            ),
+            DeckError::WrongComposition {
+                decks,
+                missing,
+                duplicated,
+            } => write!(
+                f,
+                "Not {} complete deck(s), missing [{}], duplicated [{}]",
+                decks,
+                join_cards(missing),
+                join_cards(duplicated)
+            ),
+            DeckError::DuplicateCards { decks, duplicates } => {
+                write!(f, "More copies of a card than {} deck(s) hold: ", decks)?;
+                let duplicates = duplicates
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>();
+                write!(f, "{}", duplicates.join(", "))
+            }
+        }
+    }
+}
+
+impl Error for DeckError {}
+
+fn join_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Why a card in the answer notation (`SA`, `H10`, ...) could not be parsed.
+/// `position` is the 1-based index of the token in a comma separated list of cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardParseError {
+    Empty {
+        position: usize,
+    },
+    UnknownSuit {
+        position: usize,
+        token: String,
+        suit: char,
+    },
+    MissingRank {
+        position: usize,
+        token: String,
+    },
+    UnknownRank {
+        position: usize,
+        token: String,
+        rank: char,
+    },
+    TrailingCharacters {
+        position: usize,
+        token: String,
+        trailing: String,
+    },
+}
+
+impl Display for CardParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardParseError::Empty { position } => write!(f, "token {}: empty card", position),
+            CardParseError::UnknownSuit {
+                position,
+                token,
+                suit,
+            } => write!(f, "token {} '{}': unknown suit '{}'", position, token, suit),
+            CardParseError::MissingRank { position, token } => {
+                write!(f, "token {} '{}': missing rank", position, token)
+            }
+            CardParseError::UnknownRank {
+                position,
+                token,
+                rank,
+            } => write!(f, "token {} '{}': unknown rank '{}'", position, token, rank),
+            CardParseError::TrailingCharacters {
+                position,
+                token,
+                trailing,
+            } => write!(
+                f,
+                "token {} '{}': unexpected trailing characters '{}'",
+                position, token, trailing
+            ),
+        }
+    }
+}
+
+impl Error for CardParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    OutOfCards,
+    /// A card was dealt while the round waits on a player.
+    NotDealing,
+    /// A player acted while nobody is on turn.
+    NotPlayerTurn,
+    /// The rules don't allow the action for the hand on turn.
+    ActionNotAllowed(Action),
+    NoInsuranceOffered,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::OutOfCards => write!(f, "The deck ran out of cards"),
+            GameError::NotDealing => write!(f, "The round is waiting on a player, not a card"),
+            GameError::NotPlayerTurn => write!(f, "No player is on turn"),
+            GameError::ActionNotAllowed(action) => {
+                write!(f, "{:?} is not allowed for this hand", action)
+            }
+            GameError::NoInsuranceOffered => write!(f, "Insurance is not on offer"),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+/// Why an amount can't be bet or kept as a bankroll.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BetError {
+    /// Bets must be finite and more than nothing.
+    InvalidBet(f64),
+    InvalidBankroll(f64),
+}
+
+impl Display for BetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BetError::InvalidBet(amount) => {
+                write!(f, "Invalid bet {}, must be a positive amount", amount)
+            }
+            BetError::InvalidBankroll(balance) => {
+                write!(f, "Invalid bankroll {}, must be a finite amount", balance)
+            }
+        }
+    }
+}
+
+impl Error for BetError {}
+
+/// Why a simulation can't be run as asked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationError {
+    Deck(DeckError),
+    /// The share of the shoe dealt before reshuffling must be above 0 and at most 1.
+    InvalidPenetration(f64),
+    /// A bet spread has to go up to at least one unit.
+    InvalidSpread(u32),
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::Deck(e) => write!(f, "{}", e),
+            SimulationError::InvalidPenetration(penetration) => write!(
+                f,
+                "Invalid penetration {}, must be above 0 and at most 1",
+                penetration
+            ),
+            SimulationError::InvalidSpread(max_units) => write!(
+                f,
+                "Invalid bet spread {}, must be at least 1 unit",
+                max_units
+            ),
+        }
+    }
+}
+
+impl Error for SimulationError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    Deck(DeckError),
+    /// Too much of the deck is unknown to go through every way it can play out, more than
+    /// this many distinct states.
+    TooManyStates(usize),
+    /// The table refused something the strategy did.
+    Game(GameError),
+}
+
+impl From<DeckError> for AnalysisError {
+    fn from(error: DeckError) -> Self {
+        AnalysisError::Deck(error)
+    }
+}
+
+impl From<GameError> for AnalysisError {
+    fn from(error: GameError) -> Self {
+        AnalysisError::Game(error)
+    }
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::Deck(error) => error.fmt(f),
+            AnalysisError::TooManyStates(max) => write!(
+                f,
+                "More than {} states to analyze, give more of the front of the deck",
+                max
+            ),
+            AnalysisError::Game(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for AnalysisError {}
+
+/// A term of a solver goal that could not be read. `position` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoalParseError {
+    MissingValue {
+        position: usize,
+        term: String,
+    },
+    UnknownKey {
+        position: usize,
+        term: String,
+    },
+    InvalidValue {
+        position: usize,
+        term: String,
+    },
+    /// A hand goal read on its own that isn't a total, `bust` or `blackjack`.
+    InvalidHand(String),
+}
+
+impl Display for GoalParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoalParseError::MissingValue { position, term } => {
+                write!(f, "term {} '{}': expected key:value", position, term)
+            }
+            GoalParseError::UnknownKey { position, term } => write!(
+                f,
+                "term {} '{}': unknown key, use outcome, player, dealer, player_draws or dealer_draws",
+                position, term
+            ),
+            GoalParseError::InvalidValue { position, term } => {
+                write!(f, "term {} '{}': invalid value", position, term)
+            }
+            GoalParseError::InvalidHand(hand) => write!(
+                f,
+                "'{}': invalid hand, use a total like 20, soft18 or hard12, or bust or blackjack",
+                hand
+            ),
+        }
+    }
+}
+
+impl Error for GoalParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// No deck plays out to the goal under the rules and strategy.
+    Unreachable,
+    /// The search gave up after this many dead ends.
+    TooManyStates(usize),
+    /// The table refused something the strategy did.
+    Game(GameError),
+}
+
+impl From<GameError> for SolveError {
+    fn from(error: GameError) -> Self {
+        SolveError::Game(error)
+    }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Unreachable => write!(f, "No deck reaches the goal under these rules"),
+            SolveError::TooManyStates(max) => write!(
+                f,
+                "Gave up after {} dead ends without reaching the goal",
+                max
+            ),
+            SolveError::Game(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// Why the search for a deck two referees disagree on failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdversaryError {
+    Deck(DeckError),
+    /// The reference referee couldn't play a deck.
+    Game(GameError),
+}
+
+impl From<DeckError> for AdversaryError {
+    fn from(error: DeckError) -> Self {
+        AdversaryError::Deck(error)
+    }
+}
+
+impl From<GameError> for AdversaryError {
+    fn from(error: GameError) -> Self {
+        AdversaryError::Game(error)
+    }
+}
+
+impl Display for AdversaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdversaryError::Deck(error) => error.fmt(f),
+            AdversaryError::Game(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for AdversaryError {}
+
+/// Why a scenario registry could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// The file could not be read.
+    Io(String),
+    /// The file is not a JSON list of scenarios.
+    Format(String),
+    /// The name can't be used as a path, it has to be made of letters, digits, `-` and `_`.
+    InvalidName(String),
+    DuplicateName(String),
+    /// The cards don't parse, or hold a card more than once.
+    InvalidCards {
+        scenario: String,
+        details: Vec<String>,
+    },
+    /// The cards end the round differently from what the scenario says.
+    WrongOutcome {
+        scenario: String,
+        expected: Outcome,
+        actual: Outcome,
+    },
+}
+
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScenarioError::Io(e) => write!(f, "Could not read scenarios: {}", e),
+            ScenarioError::Format(e) => write!(f, "Invalid scenarios: {}", e),
+            ScenarioError::InvalidName(name) => write!(
+                f,
+                "Invalid scenario name '{}', use letters, digits, '-' and '_'",
+                name
+            ),
+            ScenarioError::DuplicateName(name) => {
+                write!(f, "Scenario '{}' is defined more than once", name)
+            }
+            ScenarioError::InvalidCards { scenario, details } => {
+                write!(f, "Scenario '{}': {}", scenario, details.join(", "))
+            }
+            ScenarioError::WrongOutcome {
+                scenario,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Scenario '{}' ends in {:?}, not {:?}",
+                scenario, actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for ScenarioError {}
+
+/// Why cards given by role can't be dealt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoleError {
+    InvalidCard {
+        role: Role,
+        error: CardParseError,
+    },
+    /// The deal gives the player and the dealer two cards each.
+    TooManyCards {
+        role: Role,
+        cards: usize,
+    },
+    /// The card at `position` of `role` is one copy more than the shoe has, the last copy
+    /// went to `taken_by`.
+    Conflict {
+        role: Role,
+        position: usize,
+        card: Card,
+        taken_by: Role,
+    },
+    /// The deal and the hits take more cards than the shoe has.
+    TooManyForDeal {
+        cards: usize,
+        shoe: usize,
+    },
+}
+
+impl Display for RoleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoleError::InvalidCard { role, error } => write!(f, "{}: {}", role, error),
+            RoleError::TooManyCards { role, cards } => write!(
+                f,
+                "{}: {} cards, only 2 are dealt, put the rest in {}_hits",
+                role, cards, role
+            ),
+            RoleError::Conflict {
+                role,
+                position,
+                card,
+                taken_by,
+            } => write!(
+                f,
+                "{}: token {} '{}': already given to {}",
+                role, position, card, taken_by
+            ),
+            RoleError::TooManyForDeal { cards, shoe } => write!(
+                f,
+                "too many cards for the deal: {} needed, the shoe has {}",
+                cards, shoe
+            ),
+        }
+    }
+}
+
+impl Error for RoleError {}
+
+/// Why a `?order=&burn=` query doesn't name a dealing order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DealingOrderError {
+    UnknownStyle(String),
+    TooManyBurnt(usize),
+}
+
+impl Display for DealingOrderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DealingOrderError::UnknownStyle(order) => write!(
+                f,
+                "Unknown dealing order '{}', use paired or alternating",
+                order
+            ),
+            DealingOrderError::TooManyBurnt(burn) => write!(
+                f,
+                "Can't burn {} cards, at most {}",
+                burn,
+                crate::dealing::MAX_BURN
+            ),
+        }
+    }
+}
+
+impl Error for DealingOrderError {}
+
+/// Why a player's message to a table is refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// Every seat is taken.
+    Full,
+    NoRound,
+    RoundInProgress,
+    NotYourTurn,
+    Game(GameError),
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::Full => write!(
+                f,
+                "The table is full, it seats {} players",
+                crate::table::MAX_SEATS
+            ),
+            TableError::NoRound => write!(f, "No round is being played, deal one first"),
+            TableError::RoundInProgress => write!(f, "A round is already being played"),
+            TableError::NotYourTurn => write!(f, "It's not your turn"),
+            TableError::Game(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for TableError {}
+
+impl From<GameError> for TableError {
+    fn from(e: GameError) -> Self {
+        TableError::Game(e)
+    }
+}
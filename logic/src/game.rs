@@ -0,0 +1,1027 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumString;
+
+use crate::Deck;
+use crate::card::{Card, Rank};
+use crate::dealing::Receiver;
+use crate::error::GameError;
+use crate::rules::{DealerStrategy, Rules, TieRule};
+use crate::strategy::Strategy;
+
+/// How a round ended for a player.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Outcome {
+    PlayerWin,
+    DealerWin,
+    Push,
+    /// The player won with a natural blackjack.
+    Blackjack,
+    /// The player gave up the hand.
+    Surrender,
+}
+
+/// What a hand is worth: the best total, and whether an ace in it is counted as 11.
+/// A soft total can't bust on the next card, the ace drops back to 1 instead.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct HandValue {
+    pub total: u8,
+    pub soft: bool,
+}
+
+impl Display for HandValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.soft {
+            write!(f, "soft {}", self.total)
+        } else {
+            write!(f, "{}", self.total)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Hand {
+    cards: Vec<Card>,
+}
+
+impl Hand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn push(&mut self, card: Card) {
+        self.cards.push(card);
+    }
+
+    /// The total counting every ace as 1.
+    pub fn hard_total(&self) -> u8 {
+        self.cards.iter().map(|card| card.value.points()).sum()
+    }
+
+    /// The total counting one ace as 11, if the hand has an ace and that does not bust it.
+    pub fn soft_total(&self) -> Option<u8> {
+        let hard = self.hard_total();
+        let has_ace = self.cards.iter().any(|card| card.value.points() == 1);
+        (has_ace && hard + 10 <= 21).then_some(hard + 10)
+    }
+
+    /// The best value for the hand, counting an ace as 11 whenever that does not bust it.
+    pub fn value(&self) -> HandValue {
+        match self.soft_total() {
+            Some(total) => HandValue { total, soft: true },
+            None => HandValue {
+                total: self.hard_total(),
+                soft: false,
+            },
+        }
+    }
+
+    pub fn total(&self) -> u8 {
+        self.value().total
+    }
+
+    pub fn is_soft(&self) -> bool {
+        self.value().soft
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.value().total > 21
+    }
+
+    pub fn is_blackjack(&self) -> bool {
+        self.cards.len() == 2 && self.value().total == 21
+    }
+}
+
+impl FromIterator<Card> for Hand {
+    fn from_iter<T: IntoIterator<Item = Card>>(iter: T) -> Self {
+        Hand {
+            cards: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// What a player can do with the hand on turn.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumString, Eq, PartialEq, Hash)]
+#[strum(serialize_all = "lowercase")]
+pub enum Action {
+    Hit,
+    Stand,
+    /// Double the bet, take exactly one more card and stand.
+    Double,
+    /// Split a pair into two hands, each with the same bet.
+    Split,
+    /// Give up the hand for half the bet back.
+    Surrender,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Phase {
+    /// The starting hands are being dealt, in the dealing order of the rules.
+    Deal,
+    /// The dealer shows an ace, waiting for the player in `seat` to take or decline insurance.
+    Insurance {
+        seat: usize,
+    },
+    /// Waiting for the player in `seat` to act on their hand number `hand`.
+    PlayerTurn {
+        seat: usize,
+        hand: usize,
+    },
+    /// The dealer draws until the table's dealer strategy says stop.
+    DealerTurn,
+    Settled,
+}
+
+/// One of the hands a player plays, there is more than one after splitting.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct PlayerHand {
+    hand: Hand,
+    doubled: bool,
+    split: bool,
+    finished: bool,
+    outcome: Option<Outcome>,
+}
+
+impl PlayerHand {
+    fn new(hand: Hand, split: bool) -> Self {
+        PlayerHand {
+            hand,
+            doubled: false,
+            split,
+            finished: false,
+            outcome: None,
+        }
+    }
+
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    pub fn is_doubled(&self) -> bool {
+        self.doubled
+    }
+
+    /// Whether the hand came out of a split, where 21 on two cards is not a blackjack.
+    pub fn is_split(&self) -> bool {
+        self.split
+    }
+
+    pub fn is_natural(&self) -> bool {
+        !self.split && self.hand.is_blackjack()
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn is_split_ace(&self) -> bool {
+        self.split && self.hand.cards()[0].value == Rank::Ace
+    }
+
+    fn finish(&mut self, outcome: Option<Outcome>) {
+        self.finished = true;
+        self.outcome = outcome;
+    }
+}
+
+/// A player at the table and the hands they play.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Seat {
+    hands: Vec<PlayerHand>,
+    insured: bool,
+}
+
+impl Seat {
+    pub fn hands(&self) -> &[PlayerHand] {
+        &self.hands
+    }
+
+    /// Whether the player took insurance, which is even money when they have a blackjack.
+    pub fn is_insured(&self) -> bool {
+        self.insured
+    }
+}
+
+/// What the next card of a round goes to.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Draw {
+    Deal,
+    Hit,
+    Double,
+}
+
+/// The state of one round of blackjack, without a deck.
+///
+/// Every card comes in from the caller: `deal` while the round `needs_card`, and with the
+/// action when a player on turn hits or doubles. That way the same rules drive a real deck
+/// through [`Game`] and anything that wants to choose the cards itself.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Round {
+    rules: Rules,
+    seats: Vec<Seat>,
+    dealer: Hand,
+    phase: Phase,
+    dealt: usize,
+}
+
+impl Round {
+    pub fn new(rules: Rules, seats: usize) -> Self {
+        assert!(seats > 0, "A round needs at least one player");
+        let seat = Seat {
+            hands: vec![PlayerHand::new(Hand::new(), false)],
+            insured: false,
+        };
+        Round {
+            rules,
+            seats: vec![seat; seats],
+            dealer: Hand::new(),
+            phase: Phase::Deal,
+            dealt: 0,
+        }
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    pub fn dealer(&self) -> &Hand {
+        &self.dealer
+    }
+
+    /// The dealer's first card, which the players get to see.
+    pub fn dealer_up_card(&self) -> Option<&Card> {
+        self.dealer.cards().first()
+    }
+
+    /// The outcome of hand number `hand` of the player in `seat`, once it is decided.
+    pub fn outcome(&self, seat: usize, hand: usize) -> Option<Outcome> {
+        self.seats.get(seat)?.hands.get(hand)?.outcome
+    }
+
+    /// The hand on turn, if a player is on turn.
+    pub fn hand_on_turn(&self) -> Option<&PlayerHand> {
+        match self.phase {
+            Phase::PlayerTurn { seat, hand } => Some(&self.seats[seat].hands[hand]),
+            _ => None,
+        }
+    }
+
+    /// Whether the next card goes to whoever the round deals to, rather than waiting on a player.
+    /// Besides the deal and the dealer's turn, a freshly split hand waits for its second card.
+    pub fn needs_card(&self) -> bool {
+        match self.phase {
+            Phase::Deal | Phase::DealerTurn => true,
+            Phase::PlayerTurn { .. } => self
+                .hand_on_turn()
+                .is_some_and(|hand| hand.hand.cards().len() < 2),
+            _ => false,
+        }
+    }
+
+    /// The round as the players at the table see it.
+    pub fn view(&self) -> TableView {
+        let hole_card = self.dealer.cards().len() >= 2
+            && !matches!(self.phase, Phase::DealerTurn | Phase::Settled);
+        let shown = if hole_card {
+            1
+        } else {
+            self.dealer.cards().len()
+        };
+        TableView {
+            phase: self.phase,
+            seats: self.seats.clone(),
+            dealer: self.dealer.cards()[..shown].to_vec(),
+            hole_card,
+            actions: self.available_actions(),
+        }
+    }
+
+    /// What the player on turn may do with their hand, under the table rules.
+    pub fn available_actions(&self) -> Vec<Action> {
+        let Phase::PlayerTurn { seat, .. } = self.phase else {
+            return vec![];
+        };
+        let Some(player_hand) = self.hand_on_turn().filter(|_| !self.needs_card()) else {
+            return vec![];
+        };
+        let hand = &player_hand.hand;
+        let first_two = hand.cards().len() == 2;
+        let split_ace = player_hand.is_split_ace();
+        let drawing_allowed = !split_ace || self.rules.hit_split_aces;
+
+        let mut actions = vec![];
+        if drawing_allowed {
+            actions.push(Action::Hit);
+        }
+        actions.push(Action::Stand);
+        if first_two
+            && drawing_allowed
+            && self.rules.double.allows(hand.total())
+            && (!player_hand.split || self.rules.double_after_split)
+        {
+            actions.push(Action::Double);
+        }
+        let cards = hand.cards();
+        if first_two
+            && cards[0].value.points() == cards[1].value.points()
+            && self.seats[seat].hands.len() < self.rules.max_split_hands
+            && (!split_ace || self.rules.resplit_aces)
+        {
+            actions.push(Action::Split);
+        }
+        if first_two && self.rules.late_surrender && self.seats[seat].hands.len() == 1 {
+            actions.push(Action::Surrender);
+        }
+        actions
+    }
+
+    pub fn deal(&mut self, card: Card) -> Result<(), GameError> {
+        match self.phase {
+            Phase::Deal => {
+                let order = self.rules.dealing;
+                match order.receiver(self.dealt, self.seats.len()) {
+                    Receiver::Burn => {}
+                    Receiver::Seat(seat) => self.seats[seat].hands[0].hand.push(card),
+                    Receiver::Dealer => self.dealer.push(card),
+                }
+                self.dealt += 1;
+                if self.dealt == order.deal_len(self.seats.len()) {
+                    self.offer_insurance(0);
+                }
+                Ok(())
+            }
+            Phase::PlayerTurn { seat, hand } if self.needs_card() => {
+                let player_hand = &mut self.seats[seat].hands[hand];
+                player_hand.hand.push(card);
+                // Split aces can't draw again, only re-split if the rules allow
+                if player_hand.is_split_ace()
+                    && !self.rules.hit_split_aces
+                    && !self.available_actions().contains(&Action::Split)
+                {
+                    self.seats[seat].hands[hand].finish(None);
+                    self.next_turn(seat, hand);
+                }
+                Ok(())
+            }
+            Phase::DealerTurn => {
+                self.dealer.push(card);
+                self.dealer_continues();
+                Ok(())
+            }
+            _ => Err(GameError::NotDealing),
+        }
+    }
+
+    pub fn insure(&mut self, take: bool) -> Result<(), GameError> {
+        let Phase::Insurance { seat } = self.phase else {
+            return Err(GameError::NoInsuranceOffered);
+        };
+        self.seats[seat].insured = take;
+        self.offer_insurance(seat + 1);
+        Ok(())
+    }
+
+    pub fn hit(&mut self, card: Card) -> Result<(), GameError> {
+        let (seat, hand) = self.allowed(Action::Hit)?;
+        let player_hand = &mut self.seats[seat].hands[hand];
+        player_hand.hand.push(card);
+        if player_hand.hand.is_bust() {
+            player_hand.finish(Some(Outcome::DealerWin));
+            self.next_turn(seat, hand);
+        }
+        Ok(())
+    }
+
+    pub fn stand(&mut self) -> Result<(), GameError> {
+        let (seat, hand) = self.allowed(Action::Stand)?;
+        self.seats[seat].hands[hand].finish(None);
+        self.next_turn(seat, hand);
+        Ok(())
+    }
+
+    pub fn double(&mut self, card: Card) -> Result<(), GameError> {
+        let (seat, hand) = self.allowed(Action::Double)?;
+        let player_hand = &mut self.seats[seat].hands[hand];
+        player_hand.doubled = true;
+        player_hand.hand.push(card);
+        let outcome = player_hand.hand.is_bust().then_some(Outcome::DealerWin);
+        player_hand.finish(outcome);
+        self.next_turn(seat, hand);
+        Ok(())
+    }
+
+    /// Splits the pair on turn into two hands. The first hand then `needs_card`, the second one
+    /// gets its card when the player gets to it.
+    pub fn split(&mut self) -> Result<(), GameError> {
+        let (seat, hand) = self.allowed(Action::Split)?;
+        let hands = &mut self.seats[seat].hands;
+        let second = hands[hand].hand.cards[1].clone();
+        hands[hand] = PlayerHand::new(Hand::from_iter([hands[hand].hand.cards[0].clone()]), true);
+        hands.insert(hand + 1, PlayerHand::new(Hand::from_iter([second]), true));
+        Ok(())
+    }
+
+    pub fn surrender(&mut self) -> Result<(), GameError> {
+        let (seat, hand) = self.allowed(Action::Surrender)?;
+        self.seats[seat].hands[hand].finish(Some(Outcome::Surrender));
+        self.next_turn(seat, hand);
+        Ok(())
+    }
+
+    pub(crate) fn allowed(&self, action: Action) -> Result<(usize, usize), GameError> {
+        let Phase::PlayerTurn { seat, hand } = self.phase else {
+            return Err(GameError::NotPlayerTurn);
+        };
+        if self.available_actions().contains(&action) {
+            Ok((seat, hand))
+        } else {
+            Err(GameError::ActionNotAllowed(action))
+        }
+    }
+
+    /// Asks the players from `seat` on about insurance, or checks the dealer for blackjack once
+    /// everybody answered.
+    fn offer_insurance(&mut self, seat: usize) {
+        let dealer_shows_ace = self
+            .dealer_up_card()
+            .is_some_and(|card| card.value == Rank::Ace);
+        if self.rules.insurance && dealer_shows_ace && seat < self.seats.len() {
+            self.phase = Phase::Insurance { seat };
+        } else {
+            self.start_turns();
+        }
+    }
+
+    fn start_turns(&mut self) {
+        let tie = self.tie();
+        let dealer_blackjack = self.dealer.is_blackjack();
+        for seat in &mut self.seats {
+            let player_hand = &mut seat.hands[0];
+            if dealer_blackjack {
+                let outcome = if player_hand.is_natural() {
+                    tie
+                } else {
+                    Outcome::DealerWin
+                };
+                player_hand.finish(Some(outcome));
+            } else if player_hand.is_natural() {
+                player_hand.finish(Some(Outcome::Blackjack));
+            }
+        }
+        self.next_turn(0, 0);
+    }
+
+    /// Hands the turn to the first unfinished hand from `hand` of `seat` on, or to the dealer.
+    fn next_turn(&mut self, seat: usize, hand: usize) {
+        let next = self
+            .seats
+            .iter()
+            .enumerate()
+            .skip(seat)
+            .flat_map(|(s, player)| {
+                player
+                    .hands
+                    .iter()
+                    .enumerate()
+                    .map(move |(h, ph)| (s, h, ph))
+            })
+            .find(|(s, h, ph)| (*s, *h) >= (seat, hand) && !ph.finished);
+        if let Some((seat, hand, _)) = next {
+            self.phase = Phase::PlayerTurn { seat, hand };
+        } else if self.live_hands().next().is_some() {
+            self.phase = Phase::DealerTurn;
+            self.dealer_continues();
+        } else {
+            self.phase = Phase::Settled;
+        }
+    }
+
+    /// Plays on with every player following `strategy` until the round needs a card: answers
+    /// insurance and takes the actions that don't draw. Returns what the next card is for, or
+    /// `None` once the round is settled.
+    pub(crate) fn play_until_draw<S>(&mut self, strategy: &S) -> Result<Option<Draw>, GameError>
+    where
+        S: Strategy + ?Sized,
+    {
+        loop {
+            if self.needs_card() {
+                return Ok(Some(Draw::Deal));
+            }
+            match self.phase {
+                Phase::Insurance { seat } => {
+                    self.insure(strategy.insure(self.seats[seat].hands[0].hand()))?
+                }
+                Phase::PlayerTurn { .. } => {
+                    let hand = self.hand_on_turn().expect("A hand is on turn").hand();
+                    let up_card = self.dealer_up_card().expect("Dealer has cards");
+                    let action = strategy.decide(hand, up_card, &self.available_actions());
+                    match action {
+                        Action::Hit => return self.allowed(action).map(|_| Some(Draw::Hit)),
+                        Action::Double => return self.allowed(action).map(|_| Some(Draw::Double)),
+                        Action::Stand => self.stand()?,
+                        Action::Split => self.split()?,
+                        Action::Surrender => self.surrender()?,
+                    }
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    /// Hands the round the card `play_until_draw` asked for.
+    pub(crate) fn take(&mut self, draw: Draw, card: Card) -> Result<(), GameError> {
+        match draw {
+            Draw::Deal => self.deal(card),
+            Draw::Hit => self.hit(card),
+            Draw::Double => self.double(card),
+        }
+    }
+
+    /// The same round with the cards after the first of every hand sorted, so rounds that only
+    /// differ in the order the later cards came in compare equal. The first card stays put, it
+    /// is the dealer's up card and tells split aces apart.
+    pub(crate) fn normalized(mut self) -> Round {
+        let hands = self
+            .seats
+            .iter_mut()
+            .flat_map(|seat| seat.hands.iter_mut().map(|hand| &mut hand.hand))
+            .chain([&mut self.dealer]);
+        for hand in hands {
+            if let Some((_, rest)) = hand.cards.split_first_mut() {
+                rest.sort();
+            }
+        }
+        self
+    }
+
+    /// Hands still waiting on the dealer to be decided.
+    fn live_hands(&self) -> impl Iterator<Item = &PlayerHand> {
+        self.seats
+            .iter()
+            .flat_map(|seat| &seat.hands)
+            .filter(|hand| hand.outcome.is_none())
+    }
+
+    fn dealer_continues(&mut self) {
+        let dealer = self.dealer.value();
+        let draws = match self.rules.dealer_strategy {
+            DealerStrategy::BeatPlayer => {
+                let best_standing = self
+                    .live_hands()
+                    .map(|hand| hand.hand.total())
+                    .max()
+                    .unwrap_or_default();
+                dealer.total <= best_standing
+            }
+            DealerStrategy::Casino => {
+                dealer.total < 17
+                    || (dealer.total == 17 && dealer.soft && self.rules.dealer_hits_soft_17)
+            }
+        };
+        if !draws {
+            self.settle();
+        }
+    }
+
+    fn tie(&self) -> Outcome {
+        match self.rules.ties {
+            TieRule::Push => Outcome::Push,
+            TieRule::DealerWins => Outcome::DealerWin,
+        }
+    }
+
+    fn settle(&mut self) {
+        let tie = self.tie();
+        let dealer = self.dealer.total();
+        let dealer_bust = self.dealer.is_bust();
+        for player_hand in self.seats.iter_mut().flat_map(|seat| &mut seat.hands) {
+            if player_hand.outcome.is_none() {
+                let player = player_hand.hand.total();
+                player_hand.outcome = Some(if dealer_bust || player > dealer {
+                    Outcome::PlayerWin
+                } else if player < dealer {
+                    Outcome::DealerWin
+                } else {
+                    tie
+                });
+            }
+        }
+        self.phase = Phase::Settled;
+    }
+}
+
+/// What the players see of a round: all of their own cards, but only the dealer's face up
+/// ones. The dealer's hole card is turned when the dealer plays or the round is settled.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct TableView {
+    pub phase: Phase,
+    pub seats: Vec<Seat>,
+    pub dealer: Vec<Card>,
+    /// Whether the dealer has a card face down.
+    pub hole_card: bool,
+    /// What the player on turn may do.
+    pub actions: Vec<Action>,
+}
+
+/// A [`Round`] dealt from a [`Deck`].
+#[derive(Debug, Clone)]
+pub struct Game {
+    deck: Deck,
+    round: Round,
+}
+
+impl Game {
+    /// Deals the starting hands for `seats` players from the top of `deck`.
+    pub fn new(deck: Deck, rules: Rules, seats: usize) -> Result<Self, GameError> {
+        let mut game = Game {
+            deck,
+            round: Round::new(rules, seats),
+        };
+        game.deal_while_needed()?;
+        Ok(game)
+    }
+
+    pub fn round(&self) -> &Round {
+        &self.round
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// Hands back the deck, to deal the next round from what is left of it.
+    pub fn into_deck(self) -> Deck {
+        self.deck
+    }
+
+    pub fn insure(&mut self, take: bool) -> Result<(), GameError> {
+        self.round.insure(take)?;
+        self.deal_while_needed()
+    }
+
+    pub fn act(&mut self, action: Action) -> Result<(), GameError> {
+        match action {
+            Action::Hit => {
+                let card = self.draw_for(action)?;
+                self.round.hit(card)?;
+            }
+            Action::Stand => self.round.stand()?,
+            Action::Double => {
+                let card = self.draw_for(action)?;
+                self.round.double(card)?;
+            }
+            Action::Split => self.round.split()?,
+            Action::Surrender => self.round.surrender()?,
+        }
+        self.deal_while_needed()
+    }
+
+    /// Plays the round out, asking `strategy` about insurance and what to do with each hand
+    /// on turn.
+    pub fn play<S>(&mut self, strategy: &S) -> Result<(), GameError>
+    where
+        S: Strategy + ?Sized,
+    {
+        while let Some(draw) = self.round.play_until_draw(strategy)? {
+            let card = self.draw()?;
+            self.round.take(draw, card)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the card for a hit or a double, checking first that the action is allowed so a
+    /// refused action does not use up a card.
+    fn draw_for(&mut self, action: Action) -> Result<Card, GameError> {
+        self.round.allowed(action)?;
+        self.draw()
+    }
+
+    fn draw(&mut self) -> Result<Card, GameError> {
+        self.deck.draw().ok_or(GameError::OutOfCards)
+    }
+
+    fn deal_while_needed(&mut self) -> Result<(), GameError> {
+        while self.round.needs_card() {
+            let card = self.draw()?;
+            self.round.deal(card)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{FromAnswer, parse_cards};
+    use crate::dealing::{DealStyle, DealingOrder};
+    use crate::deck_generator::{
+        both_blackjack, complete_deck, dealer_blackjack, dealer_bust, four_aces, player_blackjack,
+        player_bust, tie21,
+    };
+    use crate::strategy::Threshold;
+
+    fn deck(cards: &str) -> Deck {
+        complete_deck(parse_cards(cards).unwrap(), 1).unwrap()
+    }
+
+    fn hand(cards: &str) -> Hand {
+        parse_cards(cards).unwrap().into_iter().collect()
+    }
+
+    fn play(deck: Deck) -> Game {
+        play_with(deck, Rules::interview())
+    }
+
+    fn play_with(deck: Deck, rules: Rules) -> Game {
+        let mut game = Game::new(deck, rules, 1).unwrap();
+        game.play(&Threshold::default()).unwrap();
+        game
+    }
+
+    #[test]
+    fn hand_totals_count_aces_as_one_or_eleven() {
+        assert_eq!(hand("SA,HA").total(), 12);
+        assert_eq!(hand("SA,HA").soft_total(), Some(12));
+        assert_eq!(hand("SA,H6").total(), 17);
+        assert_eq!(hand("SA,H6,CK").soft_total(), None);
+        assert_eq!(hand("SA,H6,CK").total(), 17);
+        assert_eq!(hand("SK,HQ").hard_total(), 20);
+    }
+
+    #[test]
+    fn hand_values_report_soft_totals() {
+        assert_eq!(
+            hand("SA,HA").value(),
+            HandValue {
+                total: 12,
+                soft: true
+            }
+        );
+        assert_eq!(hand("SA,HA,CA,DA,S7").value().to_string(), "soft 21");
+        assert_eq!(hand("SA,H6,CK").value().to_string(), "17");
+        assert!(!hand("SA,H6,CK").is_soft());
+        assert!(hand("SA,H6").is_soft());
+    }
+
+    #[test]
+    fn hands_bust_over_21_and_blackjack_on_two_cards() {
+        assert!(hand("SK,HQ,C2").is_bust());
+        assert!(!hand("SA,HA,CK,D9").is_bust());
+        assert!(hand("SA,HK").is_blackjack());
+        assert!(!hand("S5,H6,CK").is_blackjack());
+    }
+
+    #[test]
+    fn scenarios_end_the_way_they_are_named() {
+        assert_eq!(
+            play(player_blackjack(1)).round().outcome(0, 0),
+            Some(Outcome::Blackjack)
+        );
+        assert_eq!(
+            play(dealer_blackjack(1)).round().outcome(0, 0),
+            Some(Outcome::DealerWin)
+        );
+        assert_eq!(
+            play(both_blackjack(1)).round().outcome(0, 0),
+            Some(Outcome::DealerWin)
+        );
+        assert_eq!(
+            play(player_bust(1)).round().outcome(0, 0),
+            Some(Outcome::DealerWin)
+        );
+        assert_eq!(
+            play(dealer_bust(1)).round().outcome(0, 0),
+            Some(Outcome::PlayerWin)
+        );
+        assert_eq!(
+            play(tie21(1)).round().outcome(0, 0),
+            Some(Outcome::PlayerWin)
+        );
+    }
+
+    #[test]
+    fn four_aces_are_not_a_bust() {
+        let game = play(four_aces(1));
+        assert_eq!(game.round().seats()[0].hands()[0].hand().cards().len(), 4);
+        assert_eq!(game.round().seats()[0].hands()[0].hand().total(), 17);
+        assert_eq!(game.round().dealer().total(), 21);
+        assert_eq!(game.round().outcome(0, 0), Some(Outcome::DealerWin));
+    }
+
+    #[test]
+    fn dealing_follows_player_player_dealer_dealer() {
+        let game = Game::new(
+            complete_deck(parse_cards("S2,S3,S4,S5,S6,S7").unwrap(), 1).unwrap(),
+            Rules::interview(),
+            2,
+        )
+        .unwrap();
+        let round = game.round();
+
+        assert_eq!(round.seats()[0].hands()[0].hand(), &hand("S2,S3"));
+        assert_eq!(round.seats()[1].hands()[0].hand(), &hand("S4,S5"));
+        assert_eq!(round.dealer(), &hand("S6,S7"));
+        assert_eq!(
+            round.dealer_up_card(),
+            Some(&Card::from_answer("S6").unwrap())
+        );
+        assert_eq!(round.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+    }
+
+    #[test]
+    fn the_hole_card_stays_hidden_until_the_dealer_plays() {
+        let mut game = Game::new(deck("SK,S6,H10,H7,C10,D2"), Rules::interview(), 1).unwrap();
+        let view = game.round().view();
+        assert!(view.hole_card);
+        assert_eq!(view.dealer, hand("H10").cards());
+        assert_eq!(view.seats[0].hands()[0].hand(), &hand("SK,S6"));
+        assert_eq!(view.actions, [Action::Hit, Action::Stand]);
+
+        game.act(Action::Stand).unwrap();
+        let view = game.round().view();
+        assert!(!view.hole_card);
+        assert_eq!(view.dealer, hand("H10,H7").cards());
+        assert_eq!(view.phase, Phase::Settled);
+        assert!(view.actions.is_empty());
+    }
+
+    #[test]
+    fn alternating_deals_go_round_the_table_after_the_burn() {
+        let casino = Rules {
+            dealing: DealingOrder {
+                style: DealStyle::Alternating,
+                burn: 1,
+            },
+            ..Rules::casino()
+        };
+        let game = Game::new(deck("SK,S2,S3,S4,S5,S6,S7"), casino, 2).unwrap();
+        let round = game.round();
+
+        assert_eq!(round.seats()[0].hands()[0].hand(), &hand("S2,S5"));
+        assert_eq!(round.seats()[1].hands()[0].hand(), &hand("S3,S6"));
+        assert_eq!(round.dealer(), &hand("S4,S7"));
+        assert_eq!(round.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+    }
+
+    #[test]
+    fn casino_dealers_stand_on_17_and_ties_push() {
+        let tie21 = play_with(tie21(1), Rules::casino());
+        assert_eq!(tie21.round().dealer().total(), 19);
+        assert_eq!(tie21.round().outcome(0, 0), Some(Outcome::PlayerWin));
+
+        let both_blackjack = play_with(both_blackjack(1), Rules::casino());
+        assert_eq!(both_blackjack.round().outcome(0, 0), Some(Outcome::Push));
+
+        let push = play_with(deck("SK,HQ,CK,DQ"), Rules::casino());
+        assert_eq!(push.round().outcome(0, 0), Some(Outcome::Push));
+        let dealer_wins_ties = play_with(deck("SK,HQ,CK,DQ"), Rules::interview());
+        assert_eq!(dealer_wins_ties.round().dealer().cards().len(), 3);
+    }
+
+    #[test]
+    fn h17_dealers_draw_on_soft_17() {
+        let soft_17 = "SK,HQ,CA,D6,S4";
+        let s17 = play_with(deck(soft_17), Rules::casino());
+        assert_eq!(s17.round().dealer().total(), 17);
+        assert_eq!(s17.round().outcome(0, 0), Some(Outcome::PlayerWin));
+
+        let h17 = play_with(deck(soft_17), Rules::casino_h17());
+        assert_eq!(h17.round().dealer().total(), 21);
+        assert_eq!(h17.round().outcome(0, 0), Some(Outcome::DealerWin));
+    }
+
+    #[test]
+    fn split_hands_are_played_and_settled_one_by_one() {
+        let mut game = Game::new(deck("S8,H8,C10,D7,S3,DK,C2"), Rules::casino(), 1).unwrap();
+        assert_eq!(
+            game.round().available_actions(),
+            [
+                Action::Hit,
+                Action::Stand,
+                Action::Double,
+                Action::Split,
+                Action::Surrender
+            ]
+        );
+
+        game.act(Action::Split).unwrap();
+        assert_eq!(game.round().seats()[0].hands()[0].hand(), &hand("S8,S3"));
+        assert!(
+            !game
+                .round()
+                .available_actions()
+                .contains(&Action::Surrender)
+        );
+        game.act(Action::Double).unwrap();
+        assert_eq!(game.round().phase(), Phase::PlayerTurn { seat: 0, hand: 1 });
+        assert_eq!(game.round().seats()[0].hands()[1].hand(), &hand("H8,C2"));
+        game.act(Action::Stand).unwrap();
+
+        let hands = game.round().seats()[0].hands();
+        assert!(hands[0].is_doubled() && hands[0].is_split());
+        assert_eq!(hands[0].hand().total(), 21);
+        assert_eq!(hands[0].outcome(), Some(Outcome::PlayerWin));
+        assert_eq!(hands[1].outcome(), Some(Outcome::DealerWin));
+        assert_eq!(game.round().phase(), Phase::Settled);
+    }
+
+    #[test]
+    fn pairs_resplit_up_to_the_table_limit() {
+        let mut game = Game::new(deck("S8,H8,C10,D7,D8"), Rules::casino(), 1).unwrap();
+        game.act(Action::Split).unwrap();
+        assert!(game.round().available_actions().contains(&Action::Split));
+
+        let two_hands = Rules {
+            max_split_hands: 2,
+            ..Rules::casino()
+        };
+        let mut game = Game::new(deck("S8,H8,C10,D7,D8"), two_hands, 1).unwrap();
+        game.act(Action::Split).unwrap();
+        assert!(!game.round().available_actions().contains(&Action::Split));
+    }
+
+    #[test]
+    fn split_aces_get_one_card_each_and_no_blackjack() {
+        let mut game = Game::new(deck("SA,HA,C10,D7,SK,H9"), Rules::casino(), 1).unwrap();
+        game.act(Action::Split).unwrap();
+
+        let hands = game.round().seats()[0].hands();
+        assert_eq!(game.round().phase(), Phase::Settled);
+        assert_eq!(hands[0].hand(), &hand("SA,SK"));
+        assert_eq!(hands[0].outcome(), Some(Outcome::PlayerWin));
+        assert_eq!(hands[1].hand(), &hand("HA,H9"));
+        assert_eq!(hands[1].outcome(), Some(Outcome::PlayerWin));
+    }
+
+    #[test]
+    fn surrendering_ends_the_hand_without_the_dealer_drawing() {
+        let mut game = Game::new(deck("SK,H6,C10,D6"), Rules::casino(), 1).unwrap();
+        game.act(Action::Surrender).unwrap();
+
+        assert_eq!(game.round().outcome(0, 0), Some(Outcome::Surrender));
+        assert_eq!(game.round().dealer().cards().len(), 2);
+    }
+
+    #[test]
+    fn insurance_is_offered_when_the_dealer_shows_an_ace() {
+        let mut game = Game::new(deck("SK,H9,CA,DK"), Rules::casino(), 1).unwrap();
+        assert_eq!(game.round().phase(), Phase::Insurance { seat: 0 });
+        game.insure(true).unwrap();
+        assert!(game.round().seats()[0].is_insured());
+        assert_eq!(game.round().outcome(0, 0), Some(Outcome::DealerWin));
+
+        let mut even_money = Game::new(deck("SA,HK,CA,D7"), Rules::casino(), 1).unwrap();
+        even_money.insure(true).unwrap();
+        assert!(even_money.round().seats()[0].is_insured());
+        assert_eq!(even_money.round().outcome(0, 0), Some(Outcome::Blackjack));
+
+        let interview = Game::new(deck("SK,H9,CA,DK"), Rules::interview(), 1).unwrap();
+        assert_eq!(interview.round().phase(), Phase::Settled);
+    }
+
+    #[test]
+    fn actions_outside_the_rules_are_refused_without_drawing() {
+        let mut game = Game::new(deck("S8,H8,C10,D7"), Rules::interview(), 1).unwrap();
+        assert_eq!(
+            game.round().available_actions(),
+            [Action::Hit, Action::Stand]
+        );
+        assert_eq!(
+            game.act(Action::Double),
+            Err(GameError::ActionNotAllowed(Action::Double))
+        );
+        assert_eq!(
+            game.act(Action::Split),
+            Err(GameError::ActionNotAllowed(Action::Split))
+        );
+        assert_eq!(game.deck().remaining(), 48);
+        assert_eq!(game.insure(true), Err(GameError::NoInsuranceOffered));
+    }
+
+    #[test]
+    fn rounds_reject_actions_out_of_turn() {
+        let mut round = Round::new(Rules::interview(), 1);
+        let card = Card::from_answer("S2").unwrap();
+        assert_eq!(round.hit(card.clone()), Err(GameError::NotPlayerTurn));
+        assert_eq!(round.stand(), Err(GameError::NotPlayerTurn));
+
+        for answer in ["S5", "S6", "S7", "S8"] {
+            round.deal(Card::from_answer(answer).unwrap()).unwrap();
+        }
+        assert_eq!(round.deal(card), Err(GameError::NotDealing));
+    }
+}
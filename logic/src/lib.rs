@@ -1,3 +1,18 @@
+pub mod adversary;
+pub mod analyze;
+pub mod betting;
 pub mod card;
+pub mod counting;
+pub mod dealing;
+pub mod deck;
 pub mod deck_generator;
 pub mod error;
+pub mod game;
+pub mod rules;
+pub mod scenario;
+pub mod simulate;
+pub mod solve;
+pub mod strategy;
+pub mod table;
+
+pub use deck::Deck;
@@ -0,0 +1,146 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dealing::DealingOrder;
+
+/// How the dealer decides to draw another card.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum DealerStrategy {
+    /// The interview rule: the dealer draws while their total is at most the best player total
+    /// still standing, so they keep going until they beat the player or bust.
+    BeatPlayer,
+    /// The casino rule: the dealer draws below 17, and on soft 17 if the table says so.
+    Casino,
+}
+
+/// What happens when the player and the dealer end on the same total.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum TieRule {
+    Push,
+    DealerWins,
+}
+
+/// Which two card hands a player may double down on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum DoubleRule {
+    Never,
+    AnyTwoCards,
+    NineToEleven,
+    TenOrEleven,
+}
+
+impl DoubleRule {
+    pub fn allows(&self, total: u8) -> bool {
+        match self {
+            DoubleRule::Never => false,
+            DoubleRule::AnyTwoCards => true,
+            DoubleRule::NineToEleven => (9..=11).contains(&total),
+            DoubleRule::TenOrEleven => (10..=11).contains(&total),
+        }
+    }
+}
+
+/// A payout ratio, `pays` units for every `to` units bet. Blackjack usually pays 3:2.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Payout {
+    pub pays: u32,
+    pub to: u32,
+}
+
+impl Display for Payout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.pays, self.to)
+    }
+}
+
+/// The rules of the table a round is played at.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct Rules {
+    pub dealer_strategy: DealerStrategy,
+    /// Whether a casino dealer draws on soft 17 (H17) or stands on it (S17).
+    pub dealer_hits_soft_17: bool,
+    pub blackjack_payout: Payout,
+    pub ties: TieRule,
+    /// How many hands a player may end up with by splitting pairs, re-splits included.
+    /// 1 means splitting isn't allowed.
+    pub max_split_hands: usize,
+    pub resplit_aces: bool,
+    /// Whether split aces may take more than the one card each gets after the split.
+    pub hit_split_aces: bool,
+    pub double: DoubleRule,
+    pub double_after_split: bool,
+    /// Whether a player may give up half their bet on their first two cards, once the dealer
+    /// has checked for blackjack.
+    pub late_surrender: bool,
+    /// Whether insurance, or even money on a blackjack, is offered when the dealer shows an ace.
+    pub insurance: bool,
+    /// How the starting hands are dealt, and how many cards are burnt first.
+    #[serde(default)]
+    pub dealing: DealingOrder,
+}
+
+impl Rules {
+    /// The rules candidates implement: the player can only hit or stand, the dealer draws until
+    /// they beat the player, and the dealer wins ties, including when both have blackjack.
+    pub fn interview() -> Self {
+        Rules {
+            dealer_strategy: DealerStrategy::BeatPlayer,
+            dealer_hits_soft_17: false,
+            blackjack_payout: Payout { pays: 3, to: 2 },
+            ties: TieRule::DealerWins,
+            max_split_hands: 1,
+            resplit_aces: false,
+            hit_split_aces: false,
+            double: DoubleRule::Never,
+            double_after_split: false,
+            late_surrender: false,
+            insurance: false,
+            dealing: DealingOrder::default(),
+        }
+    }
+
+    /// A casino table where the dealer stands on all 17s, ties push and blackjack pays 3:2.
+    /// Pairs split up to four hands, aces only once and with one card each. Any two cards can
+    /// double, also after a split, and late surrender and insurance are offered.
+    pub fn casino() -> Self {
+        Rules {
+            dealer_strategy: DealerStrategy::Casino,
+            dealer_hits_soft_17: false,
+            blackjack_payout: Payout { pays: 3, to: 2 },
+            ties: TieRule::Push,
+            max_split_hands: 4,
+            resplit_aces: false,
+            hit_split_aces: false,
+            double: DoubleRule::AnyTwoCards,
+            double_after_split: true,
+            late_surrender: true,
+            insurance: true,
+            dealing: DealingOrder::default(),
+        }
+    }
+
+    /// A casino table where the dealer hits soft 17.
+    pub fn casino_h17() -> Self {
+        Rules {
+            dealer_hits_soft_17: true,
+            ..Rules::casino()
+        }
+    }
+
+    /// The preset called `name`: `interview`, `casino` or `casino-h17`.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "interview" => Some(Rules::interview()),
+            "casino" => Some(Rules::casino()),
+            "casino-h17" => Some(Rules::casino_h17()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::interview()
+    }
+}
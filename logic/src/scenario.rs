@@ -0,0 +1,402 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Deck;
+use crate::card::{Card, parse_cards};
+use crate::dealing::{DealingOrder, Roles};
+use crate::deck_generator::complete_deck;
+use crate::error::{DeckError, RoleError, ScenarioError};
+use crate::game::{Game, Outcome, Round};
+use crate::rules::Rules;
+use crate::strategy::{Strategy, Threshold};
+
+/// A deck with fixed cards on top, and the outcome they lead to in the interview game.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Scenario {
+    /// Also the path the scenario is served at.
+    pub name: String,
+    pub description: String,
+    /// The cards on top of the deck when it is dealt in the paired order.
+    pub cards: Vec<Card>,
+    pub outcome: Outcome,
+}
+
+impl Scenario {
+    /// A shoe of `decks` decks that deals the player and the dealer the scenario's cards in
+    /// `order`, the rest in standard order.
+    ///
+    /// Fails when the burnt cards and the scenario's cards don't fit in the shoe together.
+    pub fn deck(&self, order: DealingOrder, decks: usize) -> Result<Deck, Vec<RoleError>> {
+        let front = Roles::from_paired(&self.cards).front(order, decks)?;
+        Ok(complete_deck(front, decks).expect("Roles checked the copies of every card"))
+    }
+
+    /// How the scenario ends under the interview rules dealt in `order`, with the player
+    /// following `strategy`. The round as it stands when it settles is returned with the
+    /// outcome.
+    ///
+    /// Fails when the scenario can't be dealt in `order` from a single deck.
+    pub fn play<S>(
+        &self,
+        order: DealingOrder,
+        strategy: &S,
+    ) -> Result<(Outcome, Round), Vec<RoleError>>
+    where
+        S: Strategy + ?Sized,
+    {
+        let rules = Rules {
+            dealing: order,
+            ..Rules::interview()
+        };
+        let mut game = Game::new(self.deck(order, 1)?, rules, 1).expect("Deck has cards");
+        game.play(strategy)
+            .expect("A whole deck is enough for a round");
+        let round = game.round().clone();
+        Ok((round.outcome(0, 0).expect("Round is settled"), round))
+    }
+}
+
+/// A scenario as `/scenarios` lists it, with the path it is served at.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScenarioListing {
+    pub path: String,
+    pub description: String,
+    /// The cards forced on top of the deck, as the paired order deals them.
+    pub cards: Vec<Card>,
+    /// The outcome for the player under the interview rules, with the player hitting below 17.
+    pub outcome: Outcome,
+}
+
+impl From<&Scenario> for ScenarioListing {
+    fn from(scenario: &Scenario) -> Self {
+        ScenarioListing {
+            path: format!("/{}", scenario.name),
+            description: scenario.description.clone(),
+            cards: scenario.cards.clone(),
+            outcome: scenario.outcome,
+        }
+    }
+}
+
+/// A scenario as written in a registry file, with the cards in the answer notation,
+/// e.g. `"cards": "SA,SJ"`.
+#[derive(Deserialize)]
+struct ScenarioEntry {
+    name: String,
+    description: String,
+    cards: String,
+    outcome: Outcome,
+}
+
+/// The scenarios the server deals, read from a JSON list of scenarios.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScenarioRegistry {
+    scenarios: Vec<Scenario>,
+}
+
+impl ScenarioRegistry {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let json = fs::read_to_string(&path)
+            .map_err(|e| ScenarioError::Io(format!("{}: {}", path.as_ref().display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    /// Reads and checks every scenario: names must be unique and usable as a path, the cards
+    /// must fit in a single deck and lead to the outcome the scenario says, with the player
+    /// hitting below 17.
+    pub fn from_json(json: &str) -> Result<Self, ScenarioError> {
+        let entries: Vec<ScenarioEntry> =
+            serde_json::from_str(json).map_err(|e| ScenarioError::Format(e.to_string()))?;
+        let mut names = HashSet::new();
+        let mut scenarios = vec![];
+        for entry in entries {
+            let valid_name = !entry.name.is_empty()
+                && entry
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(ScenarioError::InvalidName(entry.name));
+            }
+            if !names.insert(entry.name.clone()) {
+                return Err(ScenarioError::DuplicateName(entry.name));
+            }
+            let invalid_cards = |details| ScenarioError::InvalidCards {
+                scenario: entry.name.clone(),
+                details,
+            };
+            let cards = parse_cards(&entry.cards).map_err(|errors| {
+                invalid_cards(errors.iter().map(ToString::to_string).collect())
+            })?;
+            if let Err(DeckError::DuplicateCards { duplicates, .. }) =
+                complete_deck(cards.clone(), 1)
+            {
+                return Err(invalid_cards(
+                    duplicates.iter().map(ToString::to_string).collect(),
+                ));
+            }
+            scenarios.push(Scenario {
+                name: entry.name,
+                description: entry.description,
+                cards,
+                outcome: entry.outcome,
+            });
+        }
+        for scenario in &scenarios {
+            let (actual, _) = scenario
+                .play(DealingOrder::default(), &Threshold::default())
+                .map_err(|errors| ScenarioError::InvalidCards {
+                    scenario: scenario.name.clone(),
+                    details: errors.iter().map(ToString::to_string).collect(),
+                })?;
+            if actual != scenario.outcome {
+                return Err(ScenarioError::WrongOutcome {
+                    scenario: scenario.name.clone(),
+                    expected: scenario.outcome,
+                    actual,
+                });
+            }
+        }
+        Ok(ScenarioRegistry { scenarios })
+    }
+
+    pub fn scenarios(&self) -> &[Scenario] {
+        &self.scenarios
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Scenario> {
+        self.scenarios.iter().find(|scenario| scenario.name == name)
+    }
+
+    pub fn listings(&self) -> Vec<ScenarioListing> {
+        self.scenarios.iter().map(ScenarioListing::from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dealing::DealStyle;
+    use crate::deck_generator::{
+        both_blackjack, dealer_blackjack, dealer_bust, four_aces, player_blackjack, player_bust,
+        standard_order, tie21,
+    };
+    use crate::strategy::{BasicStrategy, MimicDealer, NeverBust};
+
+    const SCENARIOS: &str = include_str!("../../scenarios.json");
+
+    #[test]
+    fn the_shipped_scenarios_match_the_generators() {
+        let registry = ScenarioRegistry::from_json(SCENARIOS).unwrap();
+        type Generator = fn(usize) -> Deck;
+        let generators: [(&str, Generator); 7] = [
+            ("fouraces", four_aces),
+            ("playerblackjack", player_blackjack),
+            ("dealerblackjack", dealer_blackjack),
+            ("bothblackjack", both_blackjack),
+            ("playerbust", player_bust),
+            ("dealerbust", dealer_bust),
+            ("tie21", tie21),
+        ];
+        assert_eq!(registry.scenarios().len(), generators.len());
+        assert_eq!(registry.listings()[0].path, "/fouraces");
+        for (name, generator) in generators {
+            let scenario = registry.get(name).unwrap();
+            assert_eq!(
+                scenario.deck(DealingOrder::default(), 2).unwrap(),
+                generator(2),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn broken_scenarios_are_rejected() {
+        let scenario = |name: &str, cards: &str| {
+            format!(
+                r#"{{"name":"{}","description":"","cards":"{}","outcome":"PlayerWin"}}"#,
+                name, cards
+            )
+        };
+        let registry =
+            |entries: &[String]| ScenarioRegistry::from_json(&format!("[{}]", entries.join(",")));
+
+        assert_eq!(
+            registry(&[scenario("a/b", "SA")]),
+            Err(ScenarioError::InvalidName("a/b".into()))
+        );
+        assert_eq!(
+            registry(&[scenario("twice", "SA"), scenario("twice", "SK")]),
+            Err(ScenarioError::DuplicateName("twice".into()))
+        );
+        assert_eq!(
+            registry(&[scenario("bad", "SA,ZK,SA")]),
+            Err(ScenarioError::InvalidCards {
+                scenario: "bad".into(),
+                details: vec!["token 2 'ZK': unknown suit 'Z'".into()]
+            })
+        );
+        assert_eq!(
+            registry(&[scenario("twoaces", "SA,SA")]),
+            Err(ScenarioError::InvalidCards {
+                scenario: "twoaces".into(),
+                details: vec!["token 2 'SA': duplicate card".into()]
+            })
+        );
+        assert_eq!(
+            ScenarioRegistry::from_json(
+                r#"[{"name":"bust","description":"","cards":"SK,S6,S2,S3,SQ","outcome":"PlayerWin"}]"#
+            ),
+            Err(ScenarioError::WrongOutcome {
+                scenario: "bust".into(),
+                expected: Outcome::PlayerWin,
+                actual: Outcome::DealerWin
+            })
+        );
+        assert!(matches!(
+            ScenarioRegistry::from_json("{}"),
+            Err(ScenarioError::Format(_))
+        ));
+    }
+
+    /// Players that hit below 17 like the interview player, in the different ways a client
+    /// might write it. Every scenario has to end the way it says for each of them.
+    fn interview_players() -> Vec<(&'static str, Box<dyn Strategy>)> {
+        vec![
+            ("threshold", Box::new(Threshold::default())),
+            (
+                "mimic-dealer",
+                Box::new(MimicDealer::for_rules(&Rules::interview())),
+            ),
+        ]
+    }
+
+    /// Players that play their hands differently. Their choices can change how a round that
+    /// goes on past the deal ends, but not one settled on the deal.
+    fn other_players() -> Vec<(&'static str, Box<dyn Strategy>)> {
+        vec![
+            ("never-bust", Box::new(NeverBust)),
+            (
+                "basic-strategy",
+                Box::new(BasicStrategy::for_rules(&Rules::interview())),
+            ),
+            (
+                "mimic-h17-dealer",
+                Box::new(MimicDealer { hits_soft_17: true }),
+            ),
+            ("stand-on-12", Box::new(Threshold { stand_on: 12 })),
+            ("stand-on-21", Box::new(Threshold { stand_on: 21 })),
+        ]
+    }
+
+    fn settled_on_the_deal(round: &Round) -> bool {
+        let player = round.seats()[0].hands()[0].hand();
+        player.cards().len() == 2 && (player.is_blackjack() || round.dealer().is_blackjack())
+    }
+
+    /// The orders clients deal in: the interview way, the casino way, and either after a burn.
+    fn dealing_orders() -> Vec<DealingOrder> {
+        let styles = [DealStyle::Paired, DealStyle::Alternating];
+        styles
+            .into_iter()
+            .flat_map(|style| [0, 1].map(|burn| DealingOrder { style, burn }))
+            .collect()
+    }
+
+    #[test]
+    fn every_scenario_yields_its_outcome() {
+        let registry = ScenarioRegistry::from_json(SCENARIOS).unwrap();
+        let mut failures = vec![];
+        for scenario in registry.scenarios() {
+            let (_, reference) = scenario
+                .play(DealingOrder::default(), &Threshold::default())
+                .unwrap();
+            let players = if settled_on_the_deal(&reference) {
+                interview_players()
+                    .into_iter()
+                    .chain(other_players())
+                    .collect()
+            } else {
+                interview_players()
+            };
+            for (player, strategy) in players {
+                for order in dealing_orders() {
+                    let (outcome, _) = scenario.play(order, strategy.as_ref()).unwrap();
+                    if outcome != scenario.outcome {
+                        failures.push(format!(
+                            "/{}?order={}&burn={} ends in {:?} with {}, not {:?}",
+                            scenario.name,
+                            order.style.as_ref(),
+                            order.burn,
+                            outcome,
+                            player,
+                            scenario.outcome
+                        ));
+                    }
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn scenarios_deal_the_player_their_cards_in_any_order() {
+        let registry = ScenarioRegistry::from_json(SCENARIOS).unwrap();
+        let player_blackjack = registry.get("playerblackjack").unwrap();
+        let casino = DealingOrder {
+            style: DealStyle::Alternating,
+            burn: 1,
+        };
+        let deck = player_blackjack.deck(casino, 1).unwrap();
+        assert_eq!(deck.cards()[..4], parse_cards("S2,SA,S3,SJ").unwrap()[..]);
+
+        let (outcome, round) = player_blackjack
+            .play(casino, &Threshold::default())
+            .unwrap();
+        assert_eq!(outcome, Outcome::Blackjack);
+        assert_eq!(
+            round.seats()[0].hands()[0].hand().cards(),
+            player_blackjack.cards
+        );
+    }
+
+    #[test]
+    fn rounds_past_the_deal_can_end_differently_for_other_players() {
+        let registry = ScenarioRegistry::from_json(SCENARIOS).unwrap();
+        let dealer_bust = registry.get("dealerbust").unwrap();
+        let (outcome, round) = dealer_bust
+            .play(DealingOrder::default(), &NeverBust)
+            .unwrap();
+
+        assert!(!settled_on_the_deal(&round));
+        assert_eq!(outcome, Outcome::DealerWin);
+        assert_eq!(round.seats()[0].hands()[0].hand().total(), 13);
+    }
+
+    #[test]
+    fn a_whole_deck_of_cards_cant_be_dealt_after_a_burn() {
+        let whole_deck = Scenario {
+            name: "wholedeck".into(),
+            description: String::new(),
+            cards: standard_order(1),
+            outcome: Outcome::PlayerWin,
+        };
+        let burn = DealingOrder {
+            style: DealStyle::Paired,
+            burn: 1,
+        };
+        assert_eq!(
+            whole_deck.deck(burn, 1),
+            Err(vec![RoleError::TooManyForDeal {
+                cards: 53,
+                shoe: 52
+            }])
+        );
+        assert!(whole_deck.play(burn, &Threshold::default()).is_err());
+        assert_eq!(whole_deck.deck(burn, 2).unwrap().size(), 104);
+    }
+}
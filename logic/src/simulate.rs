@@ -0,0 +1,429 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::Deck;
+use crate::betting::{Bet, settle};
+use crate::counting::{Counter, CountingSystem};
+use crate::deck_generator::{deck_count, shuffled_shoe};
+use crate::error::{GameError, SimulationError};
+use crate::game::{Game, Outcome};
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+
+/// Rounds are played in chunks, each from its own seeded shoes, so a seed gives the same report
+/// however many threads the chunks are spread over.
+const CHUNK_ROUNDS: u64 = 10_000;
+
+/// z for a two sided 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// Bets by the count: one unit up to a true count of 2, then a unit more for every point of
+/// true count above 1, up to `max_units`. KO goes by its running count instead, see
+/// [`Counter::betting_count`].
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct BetSpread {
+    system: CountingSystem,
+    max_units: u32,
+}
+
+impl BetSpread {
+    /// A spread up to `max_units`, which has to be at least one unit.
+    pub fn new(system: CountingSystem, max_units: u32) -> Result<Self, SimulationError> {
+        if max_units == 0 {
+            return Err(SimulationError::InvalidSpread(max_units));
+        }
+        Ok(BetSpread { system, max_units })
+    }
+
+    pub fn system(&self) -> CountingSystem {
+        self.system
+    }
+
+    pub fn max_units(&self) -> u32 {
+        self.max_units
+    }
+
+    pub fn bet(&self, counter: &Counter) -> Bet {
+        let units = (counter.betting_count().floor() - 1.0).clamp(1.0, f64::from(self.max_units));
+        Bet::units(units as u32)
+    }
+}
+
+/// How to run a simulation: `rounds` rounds of one seat betting one unit each round, or
+/// following `spread` when given. The shoe is checked when the simulation is built, so every
+/// simulation can be run.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct Simulation {
+    pub rules: Rules,
+    decks: usize,
+    pub rounds: u64,
+    pub seed: u64,
+    penetration: f64,
+    pub spread: Option<BetSpread>,
+}
+
+impl Simulation {
+    /// A simulation dealing from shoes of `decks` decks, three quarters of each before it is
+    /// reshuffled.
+    pub fn new(
+        rules: Rules,
+        decks: usize,
+        rounds: u64,
+        seed: u64,
+    ) -> Result<Self, SimulationError> {
+        let decks = deck_count(Some(decks)).map_err(SimulationError::Deck)?;
+        Ok(Simulation {
+            rules,
+            decks,
+            rounds,
+            seed,
+            penetration: 0.75,
+            spread: None,
+        })
+    }
+
+    /// Deals `penetration` of each shoe before reshuffling it, 0.75 deals three quarters.
+    pub fn with_penetration(self, penetration: f64) -> Result<Self, SimulationError> {
+        if penetration > 0.0 && penetration <= 1.0 {
+            Ok(Simulation {
+                penetration,
+                ..self
+            })
+        } else {
+            Err(SimulationError::InvalidPenetration(penetration))
+        }
+    }
+
+    pub fn decks(&self) -> usize {
+        self.decks
+    }
+
+    /// Share of the shoe dealt before it is reshuffled.
+    pub fn penetration(&self) -> f64 {
+        self.penetration
+    }
+}
+
+/// What a simulation found. Rates count every hand, split hands included. Money is in units,
+/// the house edge is per unit of starting bet and the variance is of the win per round.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Report {
+    pub rounds: u64,
+    pub hands: u64,
+    /// Blackjacks count as wins.
+    pub win_rate: f64,
+    /// Surrenders count as losses.
+    pub loss_rate: f64,
+    pub push_rate: f64,
+    /// Units bet over every round, including doubles, splits and insurance.
+    pub wagered: f64,
+    /// Average starting bet, 1 without a bet spread.
+    pub average_bet: f64,
+    /// The player's total win, negative when they lost.
+    pub net: f64,
+    /// What the house keeps per unit of starting bet, positive when the player loses.
+    pub house_edge: f64,
+    pub variance: f64,
+    /// 95% confidence interval for the house edge, as (low, high).
+    pub house_edge_95: (f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    rounds: u64,
+    hands: u64,
+    wins: u64,
+    losses: u64,
+    pushes: u64,
+    bets: f64,
+    wagered: f64,
+    net: f64,
+    net_squared: f64,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.bets += other.bets;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.net_squared += other.net_squared;
+    }
+
+    fn record(&mut self, game: &Game, bet: Bet) {
+        let round = game.round();
+        let settlement = settle(round, 0, bet).expect("Round is settled");
+        self.rounds += 1;
+        self.bets += bet.amount();
+        self.wagered += settlement.wagered;
+        self.net += settlement.net;
+        self.net_squared += settlement.net * settlement.net;
+        for hand in round.seats()[0].hands() {
+            self.hands += 1;
+            match hand.outcome() {
+                Some(Outcome::PlayerWin | Outcome::Blackjack) => self.wins += 1,
+                Some(Outcome::DealerWin | Outcome::Surrender) => self.losses += 1,
+                Some(Outcome::Push) | None => self.pushes += 1,
+            }
+        }
+    }
+
+    fn report(&self) -> Report {
+        let rounds = self.rounds.max(1) as f64;
+        let hands = self.hands.max(1) as f64;
+        let mean = self.net / rounds;
+        let variance = (self.net_squared / rounds - mean * mean).max(0.0);
+        let average_bet = if self.rounds == 0 {
+            1.0
+        } else {
+            self.bets / rounds
+        };
+        let edge = -mean / average_bet;
+        let margin = Z_95 * (variance / rounds).sqrt() / average_bet;
+        Report {
+            rounds: self.rounds,
+            hands: self.hands,
+            win_rate: self.wins as f64 / hands,
+            loss_rate: self.losses as f64 / hands,
+            push_rate: self.pushes as f64 / hands,
+            wagered: self.wagered,
+            average_bet,
+            net: self.net,
+            house_edge: edge,
+            variance,
+            house_edge_95: (edge - margin, edge + margin),
+        }
+    }
+}
+
+/// Plays `simulation.rounds` rounds with `strategy`, spread over every core. Fails with the
+/// first error the table gives, such as an action the strategy picked that the rules refuse.
+pub fn simulate<S>(simulation: &Simulation, strategy: &S) -> Result<Report, GameError>
+where
+    S: Strategy + Sync + ?Sized,
+{
+    let chunks = simulation.rounds.div_ceil(CHUNK_ROUNDS) as usize;
+    let next = AtomicUsize::new(0);
+    let tallies = Mutex::new(vec![Ok(Tally::default()); chunks]);
+    let threads = thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1)
+        .min(chunks);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let chunk = next.fetch_add(1, Ordering::Relaxed);
+                    if chunk >= chunks {
+                        break;
+                    }
+                    let tally = play_chunk(simulation, strategy, chunk);
+                    if tally.is_err() {
+                        // No point playing the rest
+                        next.store(chunks, Ordering::Relaxed);
+                    }
+                    tallies.lock().expect("No thread panicked")[chunk] = tally;
+                }
+            });
+        }
+    });
+
+    // Added up in chunk order, so floating point rounding doesn't depend on the threads either.
+    let mut total = Tally::default();
+    for tally in tallies.into_inner().expect("No thread panicked") {
+        total.add(&tally?);
+    }
+    Ok(total.report())
+}
+
+/// One step of SplitMix64, which spreads nearby inputs far apart.
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The seed of the shoes of `chunk`, mixed so that no two simulation seeds share a run of
+/// chunks.
+fn chunk_seed(seed: u64, chunk: usize) -> u64 {
+    split_mix(split_mix(seed).wrapping_add(chunk as u64))
+}
+
+fn play_chunk<S>(simulation: &Simulation, strategy: &S, chunk: usize) -> Result<Tally, GameError>
+where
+    S: Strategy + ?Sized,
+{
+    let first = chunk as u64 * CHUNK_ROUNDS;
+    let rounds = CHUNK_ROUNDS.min(simulation.rounds - first);
+    let mut rng = StdRng::seed_from_u64(chunk_seed(simulation.seed, chunk));
+    let system = simulation
+        .spread
+        .map_or(CountingSystem::HiLo, |spread| spread.system);
+    let mut deck = shuffled_shoe(simulation.decks, rng.random());
+    let mut counter = Counter::new(system, simulation.decks);
+    let cut_card = (deck.size() as f64 * (1.0 - simulation.penetration)) as usize;
+    let mut tally = Tally::default();
+
+    while tally.rounds < rounds {
+        if deck.remaining() <= cut_card {
+            deck = shuffled_shoe(simulation.decks, rng.random());
+            counter = Counter::new(system, simulation.decks);
+        }
+        let bet = simulation
+            .spread
+            .map_or(Bet::units(1), |spread| spread.bet(&counter));
+        let fresh = deck.remaining() == deck.size();
+        match play_round(deck, simulation.rules, strategy) {
+            Ok(game) => {
+                tally.record(&game, bet);
+                counter.see_round(game.round());
+                deck = game.into_deck();
+            }
+            // A round that runs past the end of the shoe is dealt again from a fresh one.
+            Err(GameError::OutOfCards) if !fresh => {
+                deck = shuffled_shoe(simulation.decks, rng.random());
+                counter = Counter::new(system, simulation.decks);
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(tally)
+}
+
+fn play_round<S>(deck: Deck, rules: Rules, strategy: &S) -> Result<Game, GameError>
+where
+    S: Strategy + ?Sized,
+{
+    let mut game = Game::new(deck, rules, 1)?;
+    game.play(strategy)?;
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{Card, parse_cards};
+    use crate::counting::KO_PIVOT;
+    use crate::error::DeckError;
+    use crate::game::{Action, Hand};
+    use crate::strategy::{BasicStrategy, MimicDealer};
+
+    #[test]
+    fn a_seed_makes_the_simulation_reproducible() {
+        let simulation = Simulation::new(Rules::casino(), 6, 25_000, 42).unwrap();
+        let strategy = BasicStrategy::for_rules(&simulation.rules);
+
+        let report = simulate(&simulation, &strategy).unwrap();
+        assert_eq!(report, simulate(&simulation, &strategy).unwrap());
+        assert_eq!(report.rounds, 25_000);
+        assert!(report.hands >= report.rounds);
+        assert!((report.win_rate + report.loss_rate + report.push_rate - 1.0).abs() < 1e-9);
+
+        let other_seed = Simulation {
+            seed: 43,
+            ..simulation
+        };
+        assert_ne!(report, simulate(&other_seed, &strategy).unwrap());
+    }
+
+    #[test]
+    fn chunks_of_nearby_seeds_deal_different_shoes() {
+        let chunk = CHUNK_ROUNDS as usize;
+        assert_ne!(chunk_seed(0, 1), chunk_seed(CHUNK_ROUNDS, 0));
+        assert_ne!(chunk_seed(0, chunk), chunk_seed(1, chunk - 1));
+        assert_eq!(chunk_seed(42, 3), chunk_seed(42, 3));
+    }
+
+    #[test]
+    fn shoes_penetrations_and_spreads_that_cant_be_dealt_are_refused() {
+        assert_eq!(
+            Simulation::new(Rules::casino(), 0, 10, 1),
+            Err(SimulationError::Deck(DeckError::InvalidDeckCount(0)))
+        );
+        let simulation = Simulation::new(Rules::casino(), 6, 10, 1).unwrap();
+        for penetration in [0.0, -0.5, 1.5, f64::NAN] {
+            assert!(simulation.with_penetration(penetration).is_err());
+        }
+        assert_eq!(simulation.with_penetration(1.0).unwrap().penetration(), 1.0);
+        assert_eq!(
+            BetSpread::new(CountingSystem::HiLo, 0),
+            Err(SimulationError::InvalidSpread(0))
+        );
+    }
+
+    #[test]
+    fn a_strategy_the_table_refuses_fails_the_simulation() {
+        struct AlwaysSplit;
+        impl Strategy for AlwaysSplit {
+            fn decide(&self, _: &Hand, _: &Card, _: &[Action]) -> Action {
+                Action::Split
+            }
+        }
+
+        let simulation = Simulation::new(Rules::interview(), 1, 25_000, 1).unwrap();
+        assert_eq!(
+            simulate(&simulation, &AlwaysSplit),
+            Err(GameError::ActionNotAllowed(Action::Split))
+        );
+    }
+
+    #[test]
+    fn basic_strategy_beats_mimicking_the_dealer() {
+        let simulation = Simulation::new(Rules::casino(), 6, 100_000, 7).unwrap();
+        let basic = simulate(&simulation, &BasicStrategy::for_rules(&simulation.rules)).unwrap();
+        let mimic = simulate(&simulation, &MimicDealer::for_rules(&simulation.rules)).unwrap();
+
+        assert!(basic.house_edge_95.0 < 0.02 && basic.house_edge_95.1 > -0.01);
+        assert!(basic.house_edge < mimic.house_edge);
+        assert!(basic.house_edge_95.0 < basic.house_edge);
+        assert!(basic.house_edge < basic.house_edge_95.1);
+    }
+
+    #[test]
+    fn counting_with_a_bet_spread_lowers_the_house_edge() {
+        let flat = Simulation::new(Rules::casino(), 6, 100_000, 11).unwrap();
+        let counting = Simulation {
+            spread: Some(BetSpread::new(CountingSystem::HiLo, 12).unwrap()),
+            ..flat
+        };
+        let strategy = BasicStrategy::for_rules(&flat.rules);
+        let flat = simulate(&flat, &strategy).unwrap();
+        let counting = simulate(&counting, &strategy).unwrap();
+
+        assert_eq!(flat.average_bet, 1.0);
+        assert!(counting.average_bet > 1.0);
+        assert!(counting.house_edge < flat.house_edge);
+    }
+
+    #[test]
+    fn the_spread_follows_the_true_count() {
+        let spread = BetSpread::new(CountingSystem::HiLo, 8).unwrap();
+        let mut counter = Counter::new(CountingSystem::HiLo, 1);
+        assert_eq!(spread.bet(&counter), Bet::units(1));
+        counter.see_all(&parse_cards("S2,S3,S4,S5,S6,H2,H3,H4,H5,H6,C2,C3,C4").unwrap());
+        assert_eq!(spread.bet(&counter), Bet::units(8));
+    }
+
+    #[test]
+    fn a_ko_spread_follows_the_running_count() {
+        let spread = BetSpread::new(CountingSystem::Ko, 8).unwrap();
+        // Two decks start at -4, so five low cards bring the count to +1, still one unit.
+        let mut counter = Counter::new(CountingSystem::Ko, 2);
+        counter.see_all(&parse_cards("S2,S3,S4,S5,S6").unwrap());
+        assert_eq!(spread.bet(&counter), Bet::units(1));
+        counter.see_all(&parse_cards("H2,H3,H4,H5").unwrap());
+        assert_eq!(counter.running(), KO_PIVOT + 1);
+        assert_eq!(spread.bet(&counter), Bet::units(4));
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::Deck;
+use crate::card::Card;
+use crate::deck_generator::{DECK, complete_deck};
+use crate::error::{GoalParseError, SolveError};
+use crate::game::{Hand, Outcome, Phase, Round};
+use crate::rules::{Rules, TieRule};
+use crate::strategy::Strategy;
+
+/// How many dead ends the search may run into before it gives up.
+pub const MAX_STATES: usize = 100_000;
+
+/// Point values in the order the search tries them for the next card.
+const SEARCH_ORDER: [u8; 10] = [10, 9, 8, 7, 6, 5, 4, 3, 2, 1];
+
+/// What a hand should end up as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandGoal {
+    /// A total of 21 or less, soft or hard when `soft` says so.
+    Total {
+        total: u8,
+        soft: Option<bool>,
+    },
+    Bust,
+    Blackjack,
+}
+
+impl HandGoal {
+    fn reached(&self, hand: &Hand) -> bool {
+        match self {
+            HandGoal::Total { total, soft } => {
+                !hand.is_bust()
+                    && hand.total() == *total
+                    && soft.is_none_or(|soft| hand.is_soft() == soft)
+            }
+            HandGoal::Bust => hand.is_bust(),
+            HandGoal::Blackjack => hand.is_blackjack(),
+        }
+    }
+
+    /// Whether `hand` can still get there with more cards. Hard totals only ever go up.
+    fn possible(&self, hand: &Hand) -> bool {
+        match self {
+            HandGoal::Total { total, .. } => hand.hard_total() <= *total,
+            HandGoal::Bust => true,
+            HandGoal::Blackjack => hand.cards().len() <= 2,
+        }
+    }
+}
+
+impl FromStr for HandGoal {
+    type Err = GoalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (soft, total) = match s {
+            "bust" => return Ok(HandGoal::Bust),
+            "blackjack" => return Ok(HandGoal::Blackjack),
+            _ => match (s.strip_prefix("soft"), s.strip_prefix("hard")) {
+                (Some(total), _) => (Some(true), total),
+                (_, Some(total)) => (Some(false), total),
+                _ => (None, s),
+            },
+        };
+        match total.parse() {
+            Ok(total @ 2..=21) => Ok(HandGoal::Total { total, soft }),
+            _ => Err(GoalParseError::InvalidHand(s.into())),
+        }
+    }
+}
+
+/// A description of how a one seat round should play out. Unset parts can go any way.
+///
+/// Reads from comma separated `key:value` terms, e.g.
+/// `player:soft18,dealer:bust,dealer_draws:3` or `outcome:push,player:20`. Hands are a total,
+/// optionally prefixed with `soft` or `hard`, or `bust` or `blackjack`. Draws count the cards
+/// taken after the first two.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Goal {
+    pub outcome: Option<Outcome>,
+    pub player: Option<HandGoal>,
+    pub dealer: Option<HandGoal>,
+    pub player_draws: Option<usize>,
+    pub dealer_draws: Option<usize>,
+}
+
+impl Goal {
+    /// Parses every term, collecting all the ones that are wrong.
+    pub fn parse(s: &str) -> Result<Goal, Vec<GoalParseError>> {
+        let mut goal = Goal::default();
+        let mut errors = vec![];
+        let terms = s.split(',').map(str::trim).filter(|term| !term.is_empty());
+        for (index, term) in terms.enumerate() {
+            if let Err(error) = goal.set(term, index + 1) {
+                errors.push(error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(goal)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn set(&mut self, term: &str, position: usize) -> Result<(), GoalParseError> {
+        let Some((key, value)) = term.split_once(':') else {
+            return Err(GoalParseError::MissingValue {
+                position,
+                term: term.into(),
+            });
+        };
+        let invalid = || GoalParseError::InvalidValue {
+            position,
+            term: term.into(),
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match key.trim() {
+            "outcome" => self.outcome = Some(parse_outcome(&value).ok_or_else(invalid)?),
+            "player" => self.player = Some(value.parse().map_err(|_| invalid())?),
+            "dealer" => self.dealer = Some(value.parse().map_err(|_| invalid())?),
+            "player_draws" => self.player_draws = Some(value.parse().map_err(|_| invalid())?),
+            "dealer_draws" => self.dealer_draws = Some(value.parse().map_err(|_| invalid())?),
+            _ => {
+                return Err(GoalParseError::UnknownKey {
+                    position,
+                    term: term.into(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the round, settled or not, can still end up as the goal. Once the player is
+    /// done with their first hand, it has to be the goal already.
+    fn possible(&self, round: &Round) -> bool {
+        let player = round.seats()[0].hands()[0].hand();
+        let dealer = round.dealer();
+        let player_done = match round.phase() {
+            Phase::PlayerTurn { hand, .. } => hand > 0,
+            Phase::DealerTurn | Phase::Settled => true,
+            Phase::Deal | Phase::Insurance { .. } => false,
+        };
+        if player_done && !self.player_reached(player) {
+            return false;
+        }
+        if let (Some(goal), Some(outcome)) = (self.outcome, round.outcome(0, 0))
+            && goal != outcome
+        {
+            return false;
+        }
+        self.player.is_none_or(|goal| goal.possible(player))
+            && self.dealer.is_none_or(|goal| goal.possible(dealer))
+            && self
+                .player_draws
+                .is_none_or(|draws| player.cards().len() <= draws + 2)
+            && self
+                .dealer_draws
+                .is_none_or(|draws| dealer.cards().len() <= draws + 2)
+    }
+
+    fn reached(&self, round: &Round) -> bool {
+        let player = round.seats()[0].hands()[0].hand();
+        let dealer = round.dealer();
+        self.outcome
+            .is_none_or(|outcome| round.outcome(0, 0) == Some(outcome))
+            && self.player_reached(player)
+            && self.dealer.is_none_or(|goal| goal.reached(dealer))
+            && self
+                .dealer_draws
+                .is_none_or(|draws| dealer.cards().len() == draws + 2)
+    }
+
+    fn player_reached(&self, player: &Hand) -> bool {
+        self.player.is_none_or(|goal| goal.reached(player))
+            && self
+                .player_draws
+                .is_none_or(|draws| player.cards().len() == draws + 2)
+    }
+}
+
+fn parse_outcome(value: &str) -> Option<Outcome> {
+    match value.replace(['_', '-'], "").as_str() {
+        "playerwin" => Some(Outcome::PlayerWin),
+        "dealerwin" => Some(Outcome::DealerWin),
+        "push" => Some(Outcome::Push),
+        "blackjack" => Some(Outcome::Blackjack),
+        "surrender" => Some(Outcome::Surrender),
+        _ => None,
+    }
+}
+
+/// Searches for a deck of `decks` decks that plays out to `goal` in a one seat round under
+/// `rules`, with the player following `strategy`. The cards the round uses come first, the
+/// rest of the deck follows in standard order.
+pub fn solve<S>(goal: &Goal, rules: Rules, strategy: &S, decks: usize) -> Result<Deck, SolveError>
+where
+    S: Strategy + ?Sized,
+{
+    // Outcomes the table never gives would take searching every deck to rule out.
+    let table_allows = match goal.outcome {
+        Some(Outcome::Push) => rules.ties == TieRule::Push,
+        Some(Outcome::Surrender) => rules.late_surrender,
+        _ => true,
+    };
+    if !table_allows {
+        return Err(SolveError::Unreachable);
+    }
+    let mut solver = Solver {
+        goal,
+        strategy,
+        decks,
+        front: vec![],
+        dead_ends: HashSet::new(),
+    };
+    if solver.search(Round::new(rules, 1), [0; 10])? {
+        Ok(
+            complete_deck(solver.front, decks)
+                .expect("The search only uses cards left in the deck"),
+        )
+    } else {
+        Err(SolveError::Unreachable)
+    }
+}
+
+struct Solver<'a, S: ?Sized> {
+    goal: &'a Goal,
+    strategy: &'a S,
+    decks: usize,
+    /// The cards dealt so far on the path being searched.
+    front: Vec<Card>,
+    /// Rounds, with how many cards of each point value they used, that can't reach the goal.
+    dead_ends: HashSet<(Round, [u8; 10])>,
+}
+
+impl<S> Solver<'_, S>
+where
+    S: Strategy + ?Sized,
+{
+    fn search(&mut self, mut round: Round, used: [u8; 10]) -> Result<bool, SolveError> {
+        let draw = round.play_until_draw(self.strategy)?;
+        if !self.goal.possible(&round) {
+            return Ok(false);
+        }
+        let Some(draw) = draw else {
+            return Ok(self.goal.reached(&round));
+        };
+        let state = (round.clone().normalized(), used);
+        if self.dead_ends.contains(&state) {
+            return Ok(false);
+        }
+        if self.dead_ends.len() >= MAX_STATES {
+            return Err(SolveError::TooManyStates(MAX_STATES));
+        }
+
+        for points in SEARCH_ORDER {
+            let Some(card) = self.next_card(points, &used) else {
+                continue;
+            };
+            let mut next = round.clone();
+            next.take(draw, card.clone())
+                .expect("Round takes the card it asked for");
+            let mut used = used;
+            used[usize::from(points - 1)] += 1;
+            self.front.push(card);
+            if self.search(next, used)? {
+                return Ok(true);
+            }
+            self.front.pop();
+        }
+        self.dead_ends.insert(state);
+        Ok(false)
+    }
+
+    /// A card worth `points` that the deck still has, cycling through the suits and the ten
+    /// valued ranks so no card is used more often than there are decks.
+    fn next_card(&self, points: u8, used: &[u8; 10]) -> Option<Card> {
+        let cards: Vec<&Card> = DECK
+            .iter()
+            .filter(|card| card.value.points() == points)
+            .collect();
+        let used = usize::from(used[usize::from(points - 1)]);
+        (used < cards.len() * self.decks).then(|| cards[used % cards.len()].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::GameError;
+    use crate::game::{Action, Game};
+    use crate::strategy::Threshold;
+
+    fn play(deck: Deck, rules: Rules) -> Game {
+        let mut game = Game::new(deck, rules, 1).unwrap();
+        game.play(&Threshold::default()).unwrap();
+        game
+    }
+
+    fn solved(goal: &str, rules: Rules) -> Game {
+        let goal = Goal::parse(goal).unwrap();
+        let deck = solve(&goal, rules, &Threshold::default(), 1).unwrap();
+        play(deck, rules)
+    }
+
+    #[test]
+    fn player_stands_on_soft_18_and_the_dealer_busts_on_the_third_card() {
+        let game = solved(
+            "player:soft18, dealer:bust, dealer_draws:3",
+            Rules::interview(),
+        );
+        let round = game.round();
+        let player = round.seats()[0].hands()[0].hand();
+
+        assert_eq!(player.total(), 18);
+        assert!(player.is_soft());
+        assert!(round.dealer().is_bust());
+        assert_eq!(round.dealer().cards().len(), 5);
+        assert_eq!(round.outcome(0, 0), Some(Outcome::PlayerWin));
+    }
+
+    #[test]
+    fn push_at_20_needs_a_table_where_ties_push() {
+        let game = solved("outcome:push,player:20", Rules::casino());
+        assert_eq!(game.round().dealer().total(), 20);
+        assert_eq!(game.round().outcome(0, 0), Some(Outcome::Push));
+
+        let goal = Goal::parse("outcome:push,player:20").unwrap();
+        let interview = solve(&goal, Rules::interview(), &Threshold::default(), 1);
+        assert_eq!(interview, Err(SolveError::Unreachable));
+    }
+
+    #[test]
+    fn goals_no_deck_reaches_are_unreachable() {
+        let goal = Goal::parse("player:blackjack,player_draws:1").unwrap();
+        let result = solve(&goal, Rules::interview(), &Threshold::default(), 1);
+        assert_eq!(result, Err(SolveError::Unreachable));
+    }
+
+    #[test]
+    fn goals_report_every_bad_term() {
+        assert_eq!(
+            Goal::parse("outcome:draw,player,colour:red"),
+            Err(vec![
+                GoalParseError::InvalidValue {
+                    position: 1,
+                    term: "outcome:draw".into()
+                },
+                GoalParseError::MissingValue {
+                    position: 2,
+                    term: "player".into()
+                },
+                GoalParseError::UnknownKey {
+                    position: 3,
+                    term: "colour:red".into()
+                },
+            ])
+        );
+        assert_eq!(
+            Goal::parse("player:hard22"),
+            Err(vec![GoalParseError::InvalidValue {
+                position: 1,
+                term: "player:hard22".into()
+            }])
+        );
+    }
+
+    #[test]
+    fn hand_goals_name_the_hand_they_cant_read() {
+        assert_eq!(
+            "soft18".parse(),
+            Ok(HandGoal::Total {
+                total: 18,
+                soft: Some(true)
+            })
+        );
+        assert_eq!(
+            "hard22".parse::<HandGoal>(),
+            Err(GoalParseError::InvalidHand("hard22".into()))
+        );
+    }
+
+    #[test]
+    fn a_strategy_the_table_refuses_is_an_error() {
+        struct AlwaysSplit;
+        impl Strategy for AlwaysSplit {
+            fn decide(&self, _: &Hand, _: &Card, _: &[Action]) -> Action {
+                Action::Split
+            }
+        }
+
+        let goal = Goal::parse("outcome:player_win").unwrap();
+        assert_eq!(
+            solve(&goal, Rules::interview(), &AlwaysSplit, 1),
+            Err(SolveError::Game(GameError::ActionNotAllowed(Action::Split)))
+        );
+    }
+}
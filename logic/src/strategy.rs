@@ -0,0 +1,280 @@
+use crate::card::Card;
+use crate::game::{Action, Hand};
+use crate::rules::Rules;
+
+/// How a player decides what to do with their hand.
+pub trait Strategy {
+    /// Picks one of `actions` for `hand`, with the dealer showing `up_card`.
+    fn decide(&self, hand: &Hand, up_card: &Card, actions: &[Action]) -> Action;
+
+    /// Whether to take insurance, or even money on a blackjack, when the dealer shows an ace.
+    fn insure(&self, _hand: &Hand) -> bool {
+        false
+    }
+}
+
+/// Picks `preferred` when the table allows it, `fallback` otherwise. Stands when neither is
+/// allowed, which happens on split aces that can't take another card.
+fn pick(actions: &[Action], preferred: Action, fallback: Action) -> Action {
+    [preferred, fallback]
+        .into_iter()
+        .find(|action| actions.contains(action))
+        .unwrap_or(Action::Stand)
+}
+
+fn hit_below(hand: &Hand, total: u8, actions: &[Action]) -> Action {
+    if hand.total() < total {
+        pick(actions, Action::Hit, Action::Stand)
+    } else {
+        Action::Stand
+    }
+}
+
+/// Hits until the hand is worth `stand_on` or more, 17 by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    pub stand_on: u8,
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Threshold { stand_on: 17 }
+    }
+}
+
+impl Strategy for Threshold {
+    fn decide(&self, hand: &Hand, _: &Card, actions: &[Action]) -> Action {
+        hit_below(hand, self.stand_on, actions)
+    }
+}
+
+/// Plays like a casino dealer: hits below 17, and on soft 17 when the dealer hits it too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MimicDealer {
+    pub hits_soft_17: bool,
+}
+
+impl MimicDealer {
+    pub fn for_rules(rules: &Rules) -> Self {
+        MimicDealer {
+            hits_soft_17: rules.dealer_hits_soft_17,
+        }
+    }
+}
+
+impl Strategy for MimicDealer {
+    fn decide(&self, hand: &Hand, _: &Card, actions: &[Action]) -> Action {
+        let stand_on = if self.hits_soft_17 && hand.is_soft() {
+            18
+        } else {
+            17
+        };
+        hit_below(hand, stand_on, actions)
+    }
+}
+
+/// Only hits when the next card can't bust the hand: hard 11 or less, or soft 17 or less.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NeverBust;
+
+impl Strategy for NeverBust {
+    fn decide(&self, hand: &Hand, _: &Card, actions: &[Action]) -> Action {
+        if hand.is_soft() {
+            hit_below(hand, 18, actions)
+        } else {
+            hit_below(hand, 12, actions)
+        }
+    }
+}
+
+/// The basic strategy chart for four to eight decks with double after split and late
+/// surrender, in the stand on soft 17 (S17) or hit soft 17 (H17) version. Whatever the table
+/// doesn't allow falls back to the next best play, and insurance is always declined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BasicStrategy {
+    pub dealer_hits_soft_17: bool,
+}
+
+impl BasicStrategy {
+    pub fn for_rules(rules: &Rules) -> Self {
+        BasicStrategy {
+            dealer_hits_soft_17: rules.dealer_hits_soft_17,
+        }
+    }
+
+    fn surrender(&self, total: u8, up: u8) -> bool {
+        match total {
+            17 => self.dealer_hits_soft_17 && up == 11,
+            16 => up >= 9,
+            15 => up == 10 || (self.dealer_hits_soft_17 && up == 11),
+            _ => false,
+        }
+    }
+
+    /// Pairs are split by rank points, so any two ten-valued cards are a pair of tens.
+    fn split(&self, points: u8, up: u8) -> bool {
+        match points {
+            1 | 8 => true,
+            9 => !matches!(up, 7 | 10 | 11),
+            7 | 3 | 2 => up <= 7,
+            6 => up <= 6,
+            4 => matches!(up, 5 | 6),
+            _ => false,
+        }
+    }
+
+    fn soft(&self, total: u8, up: u8, actions: &[Action]) -> Action {
+        let h17 = self.dealer_hits_soft_17;
+        match total {
+            20.. => Action::Stand,
+            19 if h17 && up == 6 => pick(actions, Action::Double, Action::Stand),
+            19 => Action::Stand,
+            18 if (3..=6).contains(&up) || (h17 && up == 2) => {
+                pick(actions, Action::Double, Action::Stand)
+            }
+            18 if up <= 8 => Action::Stand,
+            17 if (3..=6).contains(&up) => pick(actions, Action::Double, Action::Hit),
+            15 | 16 if (4..=6).contains(&up) => pick(actions, Action::Double, Action::Hit),
+            13 | 14 if (5..=6).contains(&up) => pick(actions, Action::Double, Action::Hit),
+            _ => pick(actions, Action::Hit, Action::Stand),
+        }
+    }
+
+    fn hard(&self, total: u8, up: u8, actions: &[Action]) -> Action {
+        match total {
+            17.. => Action::Stand,
+            13..=16 if up <= 6 => Action::Stand,
+            12 if (4..=6).contains(&up) => Action::Stand,
+            11 if up <= 10 || self.dealer_hits_soft_17 => {
+                pick(actions, Action::Double, Action::Hit)
+            }
+            10 if up <= 9 => pick(actions, Action::Double, Action::Hit),
+            9 if (3..=6).contains(&up) => pick(actions, Action::Double, Action::Hit),
+            _ => pick(actions, Action::Hit, Action::Stand),
+        }
+    }
+}
+
+impl Strategy for BasicStrategy {
+    fn decide(&self, hand: &Hand, up_card: &Card, actions: &[Action]) -> Action {
+        // The charts count the dealer's ace as 11.
+        let up = match up_card.value.points() {
+            1 => 11,
+            points => points,
+        };
+        let total = hand.total();
+        if let [first, second] = hand.cards()
+            && first.value.points() == second.value.points()
+            && actions.contains(&Action::Split)
+            && self.split(first.value.points(), up)
+        {
+            return Action::Split;
+        }
+        if actions.contains(&Action::Surrender) && !hand.is_soft() && self.surrender(total, up) {
+            return Action::Surrender;
+        }
+        if hand.is_soft() {
+            self.soft(total, up, actions)
+        } else {
+            self.hard(total, up, actions)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{FromAnswer, parse_cards};
+
+    const ALL: [Action; 5] = [
+        Action::Hit,
+        Action::Stand,
+        Action::Double,
+        Action::Split,
+        Action::Surrender,
+    ];
+
+    fn hand(cards: &str) -> Hand {
+        parse_cards(cards).unwrap().into_iter().collect()
+    }
+
+    fn card(answer: &str) -> Card {
+        Card::from_answer(answer).unwrap()
+    }
+
+    #[test]
+    fn threshold_and_mimic_dealer_hit_below_17() {
+        let up = card("S7");
+        assert_eq!(
+            Threshold::default().decide(&hand("SK,H6"), &up, &ALL),
+            Action::Hit
+        );
+        assert_eq!(
+            Threshold::default().decide(&hand("SA,H6"), &up, &ALL),
+            Action::Stand
+        );
+
+        let h17 = MimicDealer::for_rules(&Rules::casino_h17());
+        assert_eq!(h17.decide(&hand("SA,H6"), &up, &ALL), Action::Hit);
+        assert_eq!(h17.decide(&hand("SK,H7"), &up, &ALL), Action::Stand);
+        let s17 = MimicDealer::for_rules(&Rules::casino());
+        assert_eq!(s17.decide(&hand("SA,H6"), &up, &ALL), Action::Stand);
+    }
+
+    #[test]
+    fn never_bust_stands_once_a_card_could_bust() {
+        let up = card("SK");
+        assert_eq!(NeverBust.decide(&hand("S9,H2"), &up, &ALL), Action::Hit);
+        assert_eq!(NeverBust.decide(&hand("S10,H2"), &up, &ALL), Action::Stand);
+        assert_eq!(NeverBust.decide(&hand("SA,H6"), &up, &ALL), Action::Hit);
+    }
+
+    #[test]
+    fn basic_strategy_follows_the_charts() {
+        let s17 = BasicStrategy::for_rules(&Rules::casino());
+        let h17 = BasicStrategy::for_rules(&Rules::casino_h17());
+
+        assert_eq!(s17.decide(&hand("S8,H8"), &card("SK"), &ALL), Action::Split);
+        assert_eq!(s17.decide(&hand("SK,HQ"), &card("S6"), &ALL), Action::Stand);
+        assert_eq!(
+            s17.decide(&hand("SK,H6"), &card("SK"), &ALL),
+            Action::Surrender
+        );
+        assert_eq!(s17.decide(&hand("SK,H2"), &card("S3"), &ALL), Action::Hit);
+        assert_eq!(s17.decide(&hand("SK,H2"), &card("S4"), &ALL), Action::Stand);
+        assert_eq!(s17.decide(&hand("S6,H5"), &card("SA"), &ALL), Action::Hit);
+        assert_eq!(
+            h17.decide(&hand("S6,H5"), &card("SA"), &ALL),
+            Action::Double
+        );
+        assert_eq!(s17.decide(&hand("SA,H7"), &card("S2"), &ALL), Action::Stand);
+        assert_eq!(
+            h17.decide(&hand("SA,H7"), &card("S2"), &ALL),
+            Action::Double
+        );
+        assert_eq!(s17.decide(&hand("SA,H7"), &card("S9"), &ALL), Action::Hit);
+    }
+
+    #[test]
+    fn basic_strategy_falls_back_when_the_table_says_no() {
+        let basic = BasicStrategy::default();
+        let hit_or_stand = [Action::Hit, Action::Stand];
+
+        assert_eq!(
+            basic.decide(&hand("S8,H8"), &card("SK"), &hit_or_stand),
+            Action::Hit
+        );
+        assert_eq!(
+            basic.decide(&hand("S6,H5"), &card("S6"), &hit_or_stand),
+            Action::Hit
+        );
+        assert_eq!(
+            basic.decide(&hand("SA,H7"), &card("S4"), &hit_or_stand),
+            Action::Stand
+        );
+        assert_eq!(
+            basic.decide(&hand("SA,H2"), &card("S9"), &[Action::Stand]),
+            Action::Stand
+        );
+    }
+}
@@ -0,0 +1,547 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::Deck;
+use crate::card::Card;
+use crate::deck_generator::shuffled_shoe;
+use crate::error::{GameError, TableError};
+use crate::game::{Action, Outcome, Phase, Round};
+use crate::rules::Rules;
+
+/// The most players a table seats.
+pub const MAX_SEATS: usize = 7;
+
+/// What happens at a table, in the order it happens. Every player at the table is told,
+/// except for `Seated` and `Error`, which are only for the one player they are about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TableEvent {
+    Seated {
+        table: String,
+        seat: usize,
+    },
+    Joined {
+        seat: usize,
+    },
+    Left {
+        seat: usize,
+    },
+    /// A fresh shoe of `decks` decks replaced the one that ran low.
+    Shuffled {
+        decks: usize,
+    },
+    RoundStarted {
+        seats: Vec<usize>,
+    },
+    /// A card went face down off the top before the deal.
+    Burned,
+    PlayerCard {
+        seat: usize,
+        hand: usize,
+        card: Card,
+    },
+    /// A card to the dealer, without the card when it is the hole card.
+    DealerCard {
+        card: Option<Card>,
+    },
+    Split {
+        seat: usize,
+        hand: usize,
+    },
+    /// The player in `seat` is asked whether they take insurance.
+    Insurance {
+        seat: usize,
+    },
+    /// The player in `seat` is on turn with their hand number `hand`.
+    Turn {
+        seat: usize,
+        hand: usize,
+        actions: Vec<Action>,
+    },
+    /// The dealer turns the hole card.
+    Reveal {
+        card: Card,
+    },
+    Settled {
+        dealer: Vec<Card>,
+        results: Vec<SeatResult>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// How each hand of a seat ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeatResult {
+    pub seat: usize,
+    pub outcomes: Vec<Outcome>,
+}
+
+/// What a player at the table can ask for, e.g. `{"action":"hit"}` or
+/// `{"action":"insurance","take":false}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum TableMessage {
+    /// Deals a round to everybody seated.
+    Deal,
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+    Insurance {
+        take: bool,
+    },
+}
+
+/// A table several players sit at, each in their own seat, playing rounds against the dealer
+/// from one shoe. Players act in turn, the table deals every card and tells what happened with
+/// [`TableEvent`]s.
+#[derive(Debug, Clone)]
+pub struct Table {
+    rules: Rules,
+    deck: Deck,
+    rng: StdRng,
+    seats: [bool; MAX_SEATS],
+    round: Option<Round>,
+    /// The table seat of each seat in the round.
+    players: Vec<usize>,
+    revealed: bool,
+}
+
+impl Table {
+    /// A table dealing from `deck`. Once it runs low, fresh shoes of as many decks are shuffled
+    /// from `seed`.
+    pub fn new(rules: Rules, deck: Deck, seed: u64) -> Self {
+        Table {
+            rules,
+            deck,
+            rng: StdRng::seed_from_u64(seed),
+            seats: [false; MAX_SEATS],
+            round: None,
+            players: vec![],
+            revealed: false,
+        }
+    }
+
+    /// Takes the first free seat. A seat left during a round is only free again once the round
+    /// is settled, so nobody takes over the hands the player left.
+    pub fn join(&mut self) -> Result<(usize, Vec<TableEvent>), TableError> {
+        let in_round = |seat: &usize| self.round.is_some() && self.players.contains(seat);
+        let seat = (0..MAX_SEATS)
+            .find(|seat| !self.seats[*seat] && !in_round(seat))
+            .ok_or(TableError::Full)?;
+        self.seats[seat] = true;
+        Ok((seat, vec![TableEvent::Joined { seat }]))
+    }
+
+    /// Frees `seat`. The hands the player leaves in a round stand, and they decline insurance.
+    pub fn leave(&mut self, seat: usize) -> Vec<TableEvent> {
+        self.seats[seat] = false;
+        let mut events = vec![TableEvent::Left { seat }];
+        if self.on_turn().is_some_and(|(on_turn, _)| on_turn == seat) {
+            self.play_on(&mut events)
+                .expect("Standing and declining are always allowed");
+        }
+        events
+    }
+
+    /// Whether every seat is free.
+    pub fn is_empty(&self) -> bool {
+        !self.seats.contains(&true)
+    }
+
+    pub fn handle(
+        &mut self,
+        seat: usize,
+        message: TableMessage,
+    ) -> Result<Vec<TableEvent>, TableError> {
+        let mut events = vec![];
+        match message {
+            TableMessage::Deal => self.deal(&mut events)?,
+            TableMessage::Insurance { take } => {
+                self.check_turn(seat)?;
+                self.round_mut().insure(take)?;
+                self.play_on(&mut events)?;
+            }
+            TableMessage::Hit => self.act(seat, Action::Hit, &mut events)?,
+            TableMessage::Stand => self.act(seat, Action::Stand, &mut events)?,
+            TableMessage::Double => self.act(seat, Action::Double, &mut events)?,
+            TableMessage::Split => self.act(seat, Action::Split, &mut events)?,
+            TableMessage::Surrender => self.act(seat, Action::Surrender, &mut events)?,
+        }
+        Ok(events)
+    }
+
+    fn deal(&mut self, events: &mut Vec<TableEvent>) -> Result<(), TableError> {
+        if self.round.is_some() {
+            return Err(TableError::RoundInProgress);
+        }
+        if self.deck.remaining() < self.deck.size() / 2 {
+            self.reshuffle(events);
+        }
+        self.players = (0..MAX_SEATS).filter(|&seat| self.seats[seat]).collect();
+        self.round = Some(Round::new(self.rules, self.players.len()));
+        self.revealed = false;
+        events.push(TableEvent::RoundStarted {
+            seats: self.players.clone(),
+        });
+        self.play_on(events)
+    }
+
+    fn act(
+        &mut self,
+        seat: usize,
+        action: Action,
+        events: &mut Vec<TableEvent>,
+    ) -> Result<(), TableError> {
+        let hand = self.check_turn(seat)?;
+        match action {
+            Action::Hit | Action::Double => {
+                // Checked first, so a refused action does not use up a card
+                self.round_mut().allowed(action)?;
+                self.give(events, |round, card| match action {
+                    Action::Hit => round.hit(card),
+                    _ => round.double(card),
+                })?;
+            }
+            Action::Stand => self.round_mut().stand()?,
+            Action::Split => {
+                self.round_mut().split()?;
+                events.push(TableEvent::Split { seat, hand });
+            }
+            Action::Surrender => self.round_mut().surrender()?,
+        }
+        self.play_on(events)
+    }
+
+    /// The hand on turn if `seat` is asked to decide something, 0 while insurance is offered.
+    fn check_turn(&self, seat: usize) -> Result<usize, TableError> {
+        if self.round.is_none() {
+            return Err(TableError::NoRound);
+        }
+        match self.on_turn() {
+            Some((on_turn, hand)) if on_turn == seat => Ok(hand),
+            _ => Err(TableError::NotYourTurn),
+        }
+    }
+
+    /// The table seat asked to decide something, with the hand on turn.
+    fn on_turn(&self) -> Option<(usize, usize)> {
+        match self.round.as_ref()?.phase() {
+            Phase::Insurance { seat } => Some((self.players[seat], 0)),
+            Phase::PlayerTurn { seat, hand } => Some((self.players[seat], hand)),
+            _ => None,
+        }
+    }
+
+    fn round_mut(&mut self) -> &mut Round {
+        self.round.as_mut().expect("A round is being played")
+    }
+
+    /// Deals on until a player still at the table has to decide or the round is settled,
+    /// standing for the players who left, then asks the player on turn or settles.
+    fn play_on(&mut self, events: &mut Vec<TableEvent>) -> Result<(), TableError> {
+        loop {
+            self.reveal(events);
+            if self.round_mut().needs_card() {
+                self.give(events, |round, card| round.deal(card))?;
+                continue;
+            }
+            match self.on_turn() {
+                Some((seat, _)) if !self.seats[seat] => match self.round_mut().phase() {
+                    Phase::Insurance { .. } => self.round_mut().insure(false)?,
+                    _ => self.round_mut().stand()?,
+                },
+                _ => break,
+            }
+        }
+
+        let round = self.round.as_ref().expect("A round is being played");
+        match round.phase() {
+            Phase::Insurance { seat } => events.push(TableEvent::Insurance {
+                seat: self.players[seat],
+            }),
+            Phase::PlayerTurn { seat, hand } => events.push(TableEvent::Turn {
+                seat: self.players[seat],
+                hand,
+                actions: round.available_actions(),
+            }),
+            _ => {
+                let results = round
+                    .seats()
+                    .iter()
+                    .zip(&self.players)
+                    .map(|(round_seat, &seat)| SeatResult {
+                        seat,
+                        outcomes: round_seat
+                            .hands()
+                            .iter()
+                            .map(|hand| hand.outcome().expect("Round is settled"))
+                            .collect(),
+                    })
+                    .collect();
+                events.push(TableEvent::Settled {
+                    dealer: round.dealer().cards().to_vec(),
+                    results,
+                });
+                self.round = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives the next card of the shoe to the round with `give`, telling where it went.
+    fn give(
+        &mut self,
+        events: &mut Vec<TableEvent>,
+        give: impl FnOnce(&mut Round, Card) -> Result<(), GameError>,
+    ) -> Result<(), TableError> {
+        let card = match self.deck.draw() {
+            Some(card) => card,
+            None => {
+                self.reshuffle(events);
+                self.deck.draw().expect("A fresh shoe has cards")
+            }
+        };
+        let before = self.round_mut().clone();
+        give(self.round_mut(), card.clone())?;
+        let after = self.round.as_ref().expect("A round is being played");
+        events.push(dealt(&before, after, &self.players, card));
+        Ok(())
+    }
+
+    /// Tells the hole card once the dealer has turned it.
+    fn reveal(&mut self, events: &mut Vec<TableEvent>) {
+        let round = self.round.as_ref().expect("A round is being played");
+        let turned = matches!(round.phase(), Phase::DealerTurn | Phase::Settled);
+        if let (false, true, Some(card)) = (self.revealed, turned, round.dealer().cards().get(1)) {
+            events.push(TableEvent::Reveal { card: card.clone() });
+            self.revealed = true;
+        }
+    }
+
+    fn reshuffle(&mut self, events: &mut Vec<TableEvent>) {
+        let decks = self.deck.decks();
+        self.deck = shuffled_shoe(decks, self.rng.random());
+        events.push(TableEvent::Shuffled { decks });
+    }
+}
+
+/// Where `card` went between `before` and `after`: the one hand that got a card, or burned.
+fn dealt(before: &Round, after: &Round, players: &[usize], card: Card) -> TableEvent {
+    let dealer = after.dealer().cards().len();
+    if dealer > before.dealer().cards().len() {
+        // The second card of the deal is the hole card
+        let card = (dealer != 2).then_some(card);
+        return TableEvent::DealerCard { card };
+    }
+    for (round_seat, (was, is)) in before.seats().iter().zip(after.seats()).enumerate() {
+        for (hand, is) in is.hands().iter().enumerate() {
+            let had = was
+                .hands()
+                .get(hand)
+                .map_or(0, |was| was.hand().cards().len());
+            if is.hand().cards().len() > had {
+                return TableEvent::PlayerCard {
+                    seat: players[round_seat],
+                    hand,
+                    card,
+                };
+            }
+        }
+    }
+    TableEvent::Burned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_cards;
+    use crate::dealing::{DealStyle, DealingOrder};
+    use crate::deck_generator::complete_deck;
+
+    fn table(cards: &str, rules: Rules) -> Table {
+        let deck = complete_deck(parse_cards(cards).unwrap(), 1).unwrap();
+        Table::new(rules, deck, 1)
+    }
+
+    fn card(answer: &str) -> Card {
+        parse_cards(answer).unwrap().remove(0)
+    }
+
+    #[test]
+    fn seats_take_turns_and_the_dealer_plays_last() {
+        let mut table = table("S2,S3,S4,S5,S6,S7,SK,S8,S9", Rules::interview());
+        assert_eq!(table.join().unwrap().0, 0);
+        assert_eq!(table.join().unwrap().0, 1);
+
+        let events = table.handle(1, TableMessage::Deal).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                TableEvent::RoundStarted { seats: vec![0, 1] },
+                TableEvent::PlayerCard {
+                    seat: 0,
+                    hand: 0,
+                    card: card("S2")
+                },
+                TableEvent::PlayerCard {
+                    seat: 0,
+                    hand: 0,
+                    card: card("S3")
+                },
+                TableEvent::PlayerCard {
+                    seat: 1,
+                    hand: 0,
+                    card: card("S4")
+                },
+                TableEvent::PlayerCard {
+                    seat: 1,
+                    hand: 0,
+                    card: card("S5")
+                },
+                TableEvent::DealerCard {
+                    card: Some(card("S6"))
+                },
+                TableEvent::DealerCard { card: None },
+                TableEvent::Turn {
+                    seat: 0,
+                    hand: 0,
+                    actions: vec![Action::Hit, Action::Stand]
+                },
+            ]
+        );
+        assert_eq!(
+            table.handle(1, TableMessage::Hit),
+            Err(TableError::NotYourTurn)
+        );
+
+        let events = table.handle(0, TableMessage::Hit).unwrap();
+        assert_eq!(
+            events[0],
+            TableEvent::PlayerCard {
+                seat: 0,
+                hand: 0,
+                card: card("SK")
+            }
+        );
+        table.handle(0, TableMessage::Stand).unwrap();
+        let events = table.handle(1, TableMessage::Stand).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                TableEvent::Reveal { card: card("S7") },
+                TableEvent::DealerCard {
+                    card: Some(card("S8"))
+                },
+                TableEvent::Settled {
+                    dealer: parse_cards("S6,S7,S8").unwrap(),
+                    results: vec![
+                        SeatResult {
+                            seat: 0,
+                            outcomes: vec![Outcome::DealerWin]
+                        },
+                        SeatResult {
+                            seat: 1,
+                            outcomes: vec![Outcome::DealerWin]
+                        },
+                    ]
+                },
+            ]
+        );
+        assert_eq!(
+            table.handle(0, TableMessage::Stand),
+            Err(TableError::NoRound)
+        );
+    }
+
+    #[test]
+    fn a_player_who_leaves_stands() {
+        let mut table = table("SK,SQ,S4,S5,HK,H9,S2", Rules::interview());
+        table.join().unwrap();
+        table.join().unwrap();
+        table.handle(0, TableMessage::Deal).unwrap();
+
+        let events = table.leave(0);
+        assert_eq!(events[0], TableEvent::Left { seat: 0 });
+        assert_eq!(
+            events[1],
+            TableEvent::Turn {
+                seat: 1,
+                hand: 0,
+                actions: vec![Action::Hit, Action::Stand]
+            }
+        );
+        table.leave(1);
+        assert!(table.is_empty());
+        assert_eq!(table.round, None);
+    }
+
+    #[test]
+    fn a_seat_left_during_a_round_is_not_given_away_until_it_settles() {
+        let mut table = table("SK,SQ,S4,S5,HK,H9", Rules::interview());
+        table.join().unwrap();
+        table.join().unwrap();
+        table.handle(0, TableMessage::Deal).unwrap();
+
+        assert_eq!(table.leave(1), vec![TableEvent::Left { seat: 1 }]);
+        assert_eq!(table.join().unwrap().0, 2);
+        assert_eq!(
+            table.handle(2, TableMessage::Stand),
+            Err(TableError::NotYourTurn)
+        );
+
+        let events = table.handle(0, TableMessage::Stand).unwrap();
+        assert_eq!(events[0], TableEvent::Reveal { card: card("H9") });
+        assert!(matches!(events.last(), Some(TableEvent::Settled { .. })));
+        assert_eq!(table.join().unwrap().0, 1);
+    }
+
+    #[test]
+    fn burned_cards_and_alternating_deals_are_told_as_dealt() {
+        let casino = Rules {
+            dealing: DealingOrder {
+                style: DealStyle::Alternating,
+                burn: 1,
+            },
+            insurance: false,
+            ..Rules::casino()
+        };
+        let mut table = table("S2,SK,HA,SQ,H9", casino);
+        table.join().unwrap();
+        let events = table.handle(0, TableMessage::Deal).unwrap();
+        assert_eq!(events[1], TableEvent::Burned);
+        assert_eq!(
+            events[3],
+            TableEvent::DealerCard {
+                card: Some(card("HA"))
+            }
+        );
+        assert_eq!(events[5], TableEvent::DealerCard { card: None });
+        assert!(matches!(events[6], TableEvent::Turn { seat: 0, .. }));
+    }
+
+    #[test]
+    fn messages_are_read_from_json() {
+        let message: TableMessage =
+            serde_json::from_str(r#"{"action":"insurance","take":true}"#).unwrap();
+        assert_eq!(message, TableMessage::Insurance { take: true });
+        let message: TableMessage = serde_json::from_str(r#"{"action":"deal"}"#).unwrap();
+        assert_eq!(message, TableMessage::Deal);
+
+        let event = serde_json::to_string(&TableEvent::DealerCard { card: None }).unwrap();
+        assert_eq!(event, r#"{"event":"dealer_card","card":null}"#);
+    }
+
+    #[test]
+    fn a_full_table_turns_players_away() {
+        let mut table = table("", Rules::interview());
+        for _ in 0..MAX_SEATS {
+            table.join().unwrap();
+        }
+        assert_eq!(table.join(), Err(TableError::Full));
+    }
+}
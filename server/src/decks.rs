@@ -0,0 +1,143 @@
+use logic::Deck;
+use logic::card::Card;
+use logic::deck_generator::shuffled_shoe;
+use serde::Serialize;
+
+use crate::sessions::{SessionError, Sessions};
+
+/// Decks clients deal from one call at a time.
+pub type DeckSessions = Sessions<Deck>;
+
+/// What is left of a deck.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeckState {
+    pub deck_id: String,
+    pub remaining: usize,
+    pub decks: usize,
+}
+
+/// Cards off the top of a deck, and how many are left to draw after them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Cards {
+    pub deck_id: String,
+    pub cards: Vec<Card>,
+    pub remaining: usize,
+}
+
+impl DeckSessions {
+    pub fn state(&self, id: &str) -> Result<DeckState, SessionError> {
+        self.with(id, |deck| Ok(deck_state(id, deck)))
+    }
+
+    /// Takes the top `count` cards off the deck, or none when fewer are left.
+    pub fn draw(&self, id: &str, count: usize) -> Result<Cards, SessionError> {
+        self.with(id, |deck| {
+            let cards = top(deck, count)?.to_vec();
+            for _ in 0..count {
+                deck.draw();
+            }
+            Ok(Cards {
+                deck_id: id.to_string(),
+                cards,
+                remaining: deck.remaining(),
+            })
+        })
+    }
+
+    /// The top `count` cards, left on the deck.
+    pub fn peek(&self, id: &str, count: usize) -> Result<Cards, SessionError> {
+        self.with(id, |deck| {
+            Ok(Cards {
+                deck_id: id.to_string(),
+                cards: top(deck, count)?.to_vec(),
+                remaining: deck.remaining(),
+            })
+        })
+    }
+
+    /// Puts every card drawn back and shuffles the whole shoe, reproducibly for the same seed.
+    pub fn shuffle(&self, id: &str, seed: u64) -> Result<DeckState, SessionError> {
+        self.with(id, |deck| {
+            *deck = shuffled_shoe(deck.decks(), seed);
+            Ok(deck_state(id, deck))
+        })
+    }
+}
+
+fn deck_state(id: &str, deck: &Deck) -> DeckState {
+    DeckState {
+        deck_id: id.to_string(),
+        remaining: deck.remaining(),
+        decks: deck.decks(),
+    }
+}
+
+fn top(deck: &Deck, count: usize) -> Result<&[Card], SessionError> {
+    if count == 0 {
+        return Err(SessionError::InvalidCount(count));
+    }
+    deck.cards()
+        .get(..count)
+        .ok_or(SessionError::NotEnoughCards {
+            remaining: deck.remaining(),
+            count,
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sessions::IDLE_TIMEOUT;
+    use logic::deck_generator::{four_aces, shoe};
+
+    #[test]
+    fn decks_are_dealt_a_few_cards_at_a_time() {
+        let sessions = DeckSessions::new(IDLE_TIMEOUT);
+        let id = sessions.open(four_aces(1)).unwrap();
+        let id = id.as_str();
+        assert_eq!(sessions.state(id).unwrap().remaining, 52);
+
+        let peeked = sessions.peek(id, 2).unwrap();
+        let drawn = sessions.draw(id, 2).unwrap();
+        assert_eq!(peeked.cards, four_aces(1).cards()[..2]);
+        assert_eq!(peeked.remaining, 52);
+        assert_eq!(drawn.cards, peeked.cards);
+        assert_eq!(drawn.remaining, 50);
+        assert_eq!(
+            sessions.draw(id, 2).unwrap().cards,
+            four_aces(1).cards()[2..4]
+        );
+        assert_eq!(sessions.state(id).unwrap().remaining, 48);
+    }
+
+    #[test]
+    fn drawing_more_than_is_left_takes_nothing() {
+        let sessions = DeckSessions::new(IDLE_TIMEOUT);
+        let id = sessions.open(shoe(1)).unwrap();
+        sessions.draw(&id, 50).unwrap();
+
+        assert_eq!(
+            sessions.draw(&id, 3),
+            Err(SessionError::NotEnoughCards {
+                remaining: 2,
+                count: 3
+            })
+        );
+        assert_eq!(sessions.draw(&id, 0), Err(SessionError::InvalidCount(0)));
+        assert_eq!(sessions.state(&id).unwrap().remaining, 2);
+    }
+
+    #[test]
+    fn shuffling_puts_every_card_back() {
+        let sessions = DeckSessions::new(IDLE_TIMEOUT);
+        let id = sessions.open(shoe(2)).unwrap();
+        sessions.draw(&id, 10).unwrap();
+
+        let shuffled = sessions.shuffle(&id, 7).unwrap();
+        assert_eq!(shuffled.remaining, 104);
+        assert_eq!(
+            sessions.peek(&id, 104).unwrap().cards,
+            shuffled_shoe(2, 7).cards()
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use logic::game::{Action, Game, TableView};
+use serde::Serialize;
+
+use crate::sessions::{SessionError, Sessions};
+
+/// Rounds the server deals, with clients only making the player's decisions.
+pub type GameSessions = Sessions<Game>;
+
+/// A game as the player sees it, without the dealer's hole card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameState {
+    pub game_id: String,
+    #[serde(flatten)]
+    pub table: TableView,
+}
+
+impl GameSessions {
+    pub fn state(&self, id: &str) -> Result<GameState, SessionError> {
+        self.with(id, |game| Ok(game_state(id, game)))
+    }
+
+    /// Plays `action` for the hand on turn, dealing on until the round waits on the player
+    /// again or is settled.
+    pub fn act(&self, id: &str, action: Action) -> Result<GameState, SessionError> {
+        self.with(id, |game| {
+            game.act(action)?;
+            Ok(game_state(id, game))
+        })
+    }
+
+    /// Takes or declines the insurance on offer.
+    pub fn insure(&self, id: &str, take: bool) -> Result<GameState, SessionError> {
+        self.with(id, |game| {
+            game.insure(take)?;
+            Ok(game_state(id, game))
+        })
+    }
+}
+
+fn game_state(id: &str, game: &Game) -> GameState {
+    GameState {
+        game_id: id.to_string(),
+        table: game.round().view(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sessions::IDLE_TIMEOUT;
+    use logic::card::parse_cards;
+    use logic::deck_generator::complete_deck;
+    use logic::error::GameError;
+    use logic::game::{Outcome, Phase};
+    use logic::rules::Rules;
+
+    fn game(cards: &str) -> Game {
+        let deck = complete_deck(parse_cards(cards).unwrap(), 1).unwrap();
+        Game::new(deck, Rules::interview(), 1).unwrap()
+    }
+
+    #[test]
+    fn the_player_only_decides_and_the_server_deals() {
+        let games = GameSessions::new(IDLE_TIMEOUT);
+        let id = games.open(game("SK,S2,H10,H7,C5,D2,S9")).unwrap();
+        let state = games.state(&id).unwrap();
+        assert!(state.table.hole_card);
+        assert_eq!(state.table.dealer.len(), 1);
+
+        let hit = games.act(&id, Action::Hit).unwrap();
+        assert_eq!(hit.table.seats[0].hands()[0].hand().total(), 17);
+        let stood = games.act(&id, Action::Stand).unwrap();
+        assert_eq!(stood.table.phase, Phase::Settled);
+        assert_eq!(
+            stood.table.seats[0].hands()[0].outcome(),
+            Some(Outcome::DealerWin)
+        );
+        assert_eq!(stood.table.dealer.len(), 3);
+    }
+
+    #[test]
+    fn actions_the_rules_forbid_are_refused() {
+        let games = GameSessions::new(IDLE_TIMEOUT);
+        let id = games.open(game("SK,S2,H10,H7")).unwrap();
+        assert_eq!(
+            games.act(&id, Action::Double),
+            Err(SessionError::Game(GameError::ActionNotAllowed(
+                Action::Double
+            )))
+        );
+        assert_eq!(
+            games.insure(&id, true),
+            Err(SessionError::Game(GameError::NoInsuranceOffered))
+        );
+    }
+}
@@ -1 +1,6 @@
+pub mod decks;
+pub mod games;
 pub mod metrics;
+pub mod queries;
+pub mod sessions;
+pub mod tables;
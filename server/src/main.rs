@@ -1,97 +1,805 @@
 #![warn(clippy::all)]
 
+use futures_util::{SinkExt, Stream, StreamExt, stream};
 use lazy_static::lazy_static;
-use logic::card::{Card, FromAnswer};
-use logic::deck_generator::{
-    both_blackjack, complete_deck, dealer_blackjack, dealer_bust, four_aces, player_blackjack,
-    player_bust, shuffle, tie21, BlackjackQuery,
-};
-use logic::error::ErrorMessage;
+use logic::Deck;
+use logic::adversary::{BugModel, Engine, catalog, find_disagreement};
+use logic::analyze::analyze;
+use logic::card::{Card, parse_cards};
+use logic::dealing::{DealingOrder, Roles};
+use logic::deck_generator::{complete_deck, deck_count, parse_seed, random_seed, shuffled_shoe};
+use logic::error::{AnalysisError, DeckError, ErrorMessage};
+use logic::game::{Action, Game};
+use logic::rules::Rules;
+use logic::scenario::ScenarioRegistry;
+use logic::solve::{Goal, solve};
+use logic::strategy::Threshold;
+use logic::table::{Table, TableEvent};
 use prometheus::Registry;
+use serde::Serialize;
+use server::decks::{DeckSessions, DeckState};
+use server::games::GameSessions;
+use server::metrics::Metrics;
+use server::queries::{
+    AdversarialQuery, BlackjackQuery, CountQuery, DealStreamQuery, DecksQuery, InsuranceQuery,
+    NewDeckQuery, NewGameQuery, SeedQuery, ShuffleQuery, SolveQuery, TableQuery,
+};
+use server::sessions::{IDLE_TIMEOUT, SessionError, Sessions};
+use server::tables::Tables;
 use std::convert::Infallible;
 use std::env;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::mpsc::unbounded_channel;
 use warp::http::{Response, StatusCode};
+use warp::sse::Event;
+use warp::ws::{Message, WebSocket};
 use warp::{Filter, Rejection, Reply};
-use server::metrics::Metrics;
+
+const SEED_HEADER: &str = "x-deck-seed";
+
+/// Paths of the routes other than the scenarios, which scenarios can't take.
+const ROUTES: [&str; 12] = [
+    "scenarios",
+    "decks",
+    "games",
+    "ws",
+    "deal",
+    "shuffle",
+    "custom",
+    "analyze",
+    "solve",
+    "adversarial",
+    "metrics",
+    "health",
+];
+
+type Scenarios = Arc<RwLock<ScenarioRegistry>>;
 
 lazy_static! {
     static ref REGISTRY: Registry = Registry::new();
 }
 
-async fn handle_reject(_r: Rejection) -> Result<impl Reply, Infallible> {
+fn bad_request(message: String) -> warp::reply::Response {
+    invalid(message, vec![])
+}
+
+fn invalid(message: String, details: Vec<String>) -> warp::reply::Response {
     let json = warp::reply::json(&ErrorMessage {
         code: 400,
-        message: "Invalid deck format".into(),
+        message,
+        details,
     });
+    warp::reply::with_status(json, StatusCode::BAD_REQUEST).into_response()
+}
+
+/// The scenarios the server was built with, served when there is no scenarios file.
+const DEFAULT_SCENARIOS: &str = include_str!("../../scenarios.json");
 
-    Ok(warp::reply::with_status(json, StatusCode::BAD_REQUEST))
+/// Loads the scenarios at `path`, making sure none of them hides a route.
+fn load_scenarios(path: &str) -> Result<ScenarioRegistry, String> {
+    check_routes(ScenarioRegistry::load(path).map_err(|e| e.to_string())?)
 }
 
-#[tokio::main]
-async fn main() {
-    env_logger::init();
-    let path_includes = vec![
-        "shuffle".into(),
-        "fouraces".into(),
-        "playerblackjack".into(),
-        "dealerblackjack".into(),
-        "custom".into(),
-        "playerbust".into(),
-        "dealerbust".into(),
-        "tie21".into(),
-    ];
-    let metrics = Metrics::new(&REGISTRY, &path_includes);
-    let logger = warp::log("unleash-blackjack");
+/// The scenarios at `path`, or the ones the server was built with when there is no such file.
+fn initial_scenarios(path: &str) -> Result<ScenarioRegistry, String> {
+    if Path::new(path).exists() {
+        return load_scenarios(path);
+    }
+    log::warn!("{} not found, serving the built-in scenarios", path);
+    check_routes(ScenarioRegistry::from_json(DEFAULT_SCENARIOS).map_err(|e| e.to_string())?)
+}
 
-    let shuffle = warp::path!("shuffle").and(warp::get()).map(|| {
-        let shuffled_deck = shuffle();
-        warp::reply::json(&shuffled_deck)
-    });
-    let fouraces = warp::path!("fouraces").and(warp::get()).map(|| {
-        let four_aces = four_aces();
-        warp::reply::json(&four_aces)
-    });
-    let playerblackjack = warp::path!("playerblackjack").and(warp::get()).map(|| {
-        let player_twentyone = player_blackjack();
-        warp::reply::json(&player_twentyone)
-    });
-    let dealerblackjack = warp::path!("dealerblackjack").and(warp::get()).map(|| {
-        let player_twentyone = dealer_blackjack();
-        warp::reply::json(&player_twentyone)
-    });
+/// Fails when a scenario of `registry` takes the path of a route.
+fn check_routes(registry: ScenarioRegistry) -> Result<ScenarioRegistry, String> {
+    match registry
+        .scenarios()
+        .iter()
+        .find(|scenario| ROUTES.contains(&scenario.name.as_str()))
+    {
+        Some(scenario) => Err(format!("Scenario '{}' takes a route's path", scenario.name)),
+        None => Ok(registry),
+    }
+}
+
+/// What can be done to a deck under `/decks/{id}` or a game under `/games/{id}`.
+const SESSION_ACTIONS: [&str; 9] = [
+    "draw",
+    "peek",
+    "shuffle",
+    "hit",
+    "stand",
+    "double",
+    "split",
+    "surrender",
+    "insurance",
+];
+
+/// The path segments to keep in the metrics: every route but metrics and health, every deck
+/// and game action and every scenario.
+fn path_labels(registry: &ScenarioRegistry) -> Vec<String> {
+    let routes = ROUTES
+        .iter()
+        .filter(|route| !["metrics", "health"].contains(route))
+        .chain(&SESSION_ACTIONS)
+        .chain(&["stream"]);
+    let scenarios = registry.scenarios().iter().map(|scenario| &scenario.name);
+    routes
+        .map(ToString::to_string)
+        .chain(scenarios.cloned())
+        .collect()
+}
 
-    let playerbust = warp::path!("playerbust").and(warp::get()).map(|| {
-        let playerbust = player_bust();
-        warp::reply::json(&playerbust)
+/// Reloads the scenarios from `path` on every SIGHUP. A file that doesn't load is logged and
+/// the scenarios already loaded stay.
+fn reload_on_hangup(path: String, scenarios: Scenarios, metrics: Metrics) {
+    let mut hangups = signal(SignalKind::hangup()).expect("Could not listen for SIGHUP");
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match load_scenarios(&path) {
+                Ok(registry) => {
+                    log::info!(
+                        "Reloaded {} scenarios from {}",
+                        registry.scenarios().len(),
+                        path
+                    );
+                    metrics.set_path_labels(&path_labels(&registry));
+                    *scenarios.write().expect("Lock is not poisoned") = registry;
+                }
+                Err(e) => log::error!("Keeping the scenarios already loaded: {}", e),
+            }
+        }
     });
+}
 
-    let dealerbust = warp::path!("dealerbust").and(warp::get()).map(|| {
-        let dealerbust = dealer_bust();
-        warp::reply::json(&dealerbust)
+/// Drops the `kind` of sessions left idle for too long, once a minute.
+fn expire_idle<T: Send + 'static>(sessions: Sessions<T>, kind: &'static str) {
+    tokio::spawn(async move {
+        let mut minutes = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            minutes.tick().await;
+            let expired = sessions.expire();
+            if expired > 0 {
+                log::info!("Expired {} idle {}", expired, kind);
+            }
+        }
     });
+}
+
+/// The table rules for a `rules` preset, interview when not given, dealt in the order of
+/// `order` and `burn`, or the message of the 400 to answer with.
+fn table_rules(
+    rules: Option<&str>,
+    order: Option<&str>,
+    burn: Option<usize>,
+) -> Result<Rules, String> {
+    let rules = rules
+        .map_or(Some(Rules::interview()), Rules::preset)
+        .ok_or("Unknown rules, use interview, casino or casino-h17")?;
+    let dealing = DealingOrder::parse(order, burn).map_err(|e| e.to_string())?;
+    Ok(Rules { dealing, ..rules })
+}
+
+/// The deck a `POST /decks` asks for and the seed it was shuffled with, if it was, or the
+/// 400 to answer with.
+fn new_deck(
+    q: &NewDeckQuery,
+    scenarios: &ScenarioRegistry,
+) -> Result<(Deck, Option<u64>), ErrorMessage> {
+    let error = |message: String, details: Vec<String>| ErrorMessage {
+        code: 400,
+        message,
+        details,
+    };
+    let decks = deck_count(q.decks).map_err(|e| error(e.to_string(), vec![]))?;
+    let order = DealingOrder::parse(q.order.as_deref(), q.burn)
+        .map_err(|e| error(e.to_string(), vec![]))?;
+    match (&q.scenario, &q.seed, &q.cards) {
+        (Some(name), None, None) => match scenarios.get(name) {
+            Some(scenario) => {
+                let deck = scenario.deck(order, decks).map_err(|errors| {
+                    let details = errors.iter().map(ToString::to_string).collect();
+                    error(format!("Can't deal scenario '{}'", name), details)
+                })?;
+                Ok((deck, None))
+            }
+            None => Err(error(format!("Unknown scenario '{}'", name), vec![])),
+        },
+        (None, seed, None) => {
+            let seed = seed.as_deref().map(parse_seed).transpose();
+            let seed = seed
+                .map_err(|e| error(format!("Invalid seed: {}", e), vec![]))?
+                .unwrap_or_else(random_seed);
+            Ok((shuffled_shoe(decks, seed), Some(seed)))
+        }
+        (None, None, Some(cards)) => {
+            let cards = parse_cards(cards).map_err(|errors| {
+                let details = errors.iter().map(ToString::to_string).collect();
+                error("Invalid cards".into(), details)
+            })?;
+            let deck = complete_deck(cards, decks).map_err(deck_error_message)?;
+            Ok((deck, None))
+        }
+        _ => Err(error("Give one of scenario, seed or cards".into(), vec![])),
+    }
+}
+
+/// How long `/deal/stream` waits between cards by default, and at most.
+const DEAL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_DEAL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The wait between cards an `interval_ms` asks for, or the message of the 400 to answer with.
+fn deal_interval(interval_ms: Option<u64>) -> Result<Duration, String> {
+    match interval_ms.map(Duration::from_millis) {
+        None => Ok(DEAL_INTERVAL),
+        Some(interval) if interval <= MAX_DEAL_INTERVAL => Ok(interval),
+        Some(interval) => Err(format!(
+            "Invalid interval_ms {}, at most {}",
+            interval.as_millis(),
+            MAX_DEAL_INTERVAL.as_millis()
+        )),
+    }
+}
+
+/// A `card` event for every card of `cards`, then a `deck` event with `state`, `interval`
+/// apart.
+fn deal_events(
+    cards: Vec<Card>,
+    state: DeckState,
+    interval: Duration,
+) -> impl Stream<Item = Result<Event, serde_json::Error>> + Send + 'static {
+    let cards = cards
+        .into_iter()
+        .map(|card| Event::default().event("card").json_data(card));
+    let events = cards.chain(std::iter::once(
+        Event::default().event("deck").json_data(state),
+    ));
+    stream::iter(events)
+        .enumerate()
+        .then(move |(sent, event)| async move {
+            if sent > 0 {
+                tokio::time::sleep(interval).await;
+            }
+            event
+        })
+}
+
+/// Where a player on `/ws` sits: at the table they name, or at a new one.
+enum Seating {
+    Join(String),
+    Open(Box<Table>),
+}
 
-    let both_blackjack = warp::path!("bothblackjack").and(warp::get()).map(|| {
-        let both_blackjack = both_blackjack();
-        warp::reply::json(&both_blackjack)
+/// The new table a `/ws` without a table id asks for, or the message of the 400 to answer
+/// with.
+fn new_table(q: &TableQuery) -> Result<Table, String> {
+    let rules = table_rules(q.rules.as_deref(), q.order.as_deref(), q.burn)?;
+    let decks = deck_count(q.decks).map_err(|e| e.to_string())?;
+    let seed = q.seed.as_deref().map(parse_seed).transpose();
+    let seed = seed
+        .map_err(|e| format!("Invalid seed: {}", e))?
+        .unwrap_or_else(random_seed);
+    Ok(Table::new(rules, shuffled_shoe(decks, seed), seed))
+}
+
+/// Seats the player on `socket` as `seating` says, and passes their messages to the table
+/// until they go.
+async fn sit_at_table(socket: WebSocket, tables: Tables, seating: Seating) {
+    let (mut to_player, mut from_player) = socket.split();
+    let (player, mut events) = unbounded_channel();
+    let (id, seat) = match seating {
+        Seating::Join(id) => match tables.join(&id, player) {
+            Ok(seat) => (id, seat),
+            Err(message) => {
+                let event = serde_json::to_string(&TableEvent::Error { message })
+                    .expect("Events serialize");
+                let _ = to_player.send(Message::text(event)).await;
+                let _ = to_player.close().await;
+                return;
+            }
+        },
+        Seating::Open(table) => tables.open(*table, player),
+    };
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if to_player.send(Message::text(event)).await.is_err() {
+                break;
+            }
+        }
     });
+    while let Some(Ok(message)) = from_player.next().await {
+        if let Ok(text) = message.to_str() {
+            tables.handle(&id, seat, text);
+        }
+    }
+    tables.leave(&id, seat);
+}
 
-    let tie21 = warp::path!("tie21").and(warp::get()).map(|| {
-        let tie21 = tie21();
-        warp::reply::json(&tie21)
+/// A `201 Created` for a session opened at `location`, with the seed its deck was shuffled
+/// with, if it was.
+fn created<T: Serialize>(state: &T, location: String, seed: Option<u64>) -> warp::reply::Response {
+    let reply = warp::reply::with_status(warp::reply::json(state), StatusCode::CREATED);
+    let reply = warp::reply::with_header(reply, "location", location);
+    match seed {
+        Some(seed) => {
+            warp::reply::with_header(reply, SEED_HEADER, seed.to_string()).into_response()
+        }
+        None => reply.into_response(),
+    }
+}
+
+fn session_error(e: SessionError) -> warp::reply::Response {
+    let status = match e {
+        SessionError::NotFound(_) => StatusCode::NOT_FOUND,
+        SessionError::TooManySessions => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    let json = warp::reply::json(&ErrorMessage {
+        code: status.as_u16(),
+        message: e.to_string(),
+        details: vec![],
     });
+    warp::reply::with_status(json, status).into_response()
+}
+
+/// The cards, dealing order and deck count from a `?cards=&order=&burn=&decks=` query, or
+/// one that gives the cards by role, or the 400 to answer with.
+fn front_of_deck(q: &BlackjackQuery) -> Result<(Vec<Card>, DealingOrder, usize), ErrorMessage> {
+    let error = |message: &str, details: Vec<String>| ErrorMessage {
+        code: 400,
+        message: message.into(),
+        details,
+    };
+    let decks = deck_count(q.decks).map_err(|e| error(&e.to_string(), vec![]))?;
+    let order = DealingOrder::parse(q.order.as_deref(), q.burn)
+        .map_err(|e| error(&e.to_string(), vec![]))?;
+    if !q.has_roles() {
+        let cards = parse_cards(q.cards.as_deref().unwrap_or_default()).map_err(|errors| {
+            error(
+                "Invalid cards",
+                errors.iter().map(ToString::to_string).collect(),
+            )
+        })?;
+        return Ok((cards, order, decks));
+    }
+    if q.cards.is_some() {
+        return Err(error(
+            "Give either cards or player, dealer, player_hits and dealer_hits",
+            vec![],
+        ));
+    }
+    let cards = Roles::parse(
+        q.player.as_deref(),
+        q.dealer.as_deref(),
+        q.player_hits.as_deref(),
+        q.dealer_hits.as_deref(),
+    )
+    .and_then(|roles| roles.front(order, decks))
+    .map_err(|errors| {
+        error(
+            "Invalid roles",
+            errors.iter().map(ToString::to_string).collect(),
+        )
+    })?;
+    Ok((cards, order, decks))
+}
+
+fn deck_error(e: DeckError) -> warp::reply::Response {
+    let e = deck_error_message(e);
+    invalid(e.message, e.details)
+}
+
+fn deck_error_message(e: DeckError) -> ErrorMessage {
+    let (message, details) = match e {
+        DeckError::DuplicateCards { duplicates, .. } => (
+            "Duplicate cards".into(),
+            duplicates.iter().map(ToString::to_string).collect(),
+        ),
+        e => (e.to_string(), vec![]),
+    };
+    ErrorMessage {
+        code: 400,
+        message,
+        details,
+    }
+}
+
+async fn handle_reject(_r: Rejection) -> Result<impl Reply, Infallible> {
+    Ok(bad_request("Invalid deck format".into()))
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let scenarios_path = env::var("SCENARIOS").unwrap_or_else(|_| "scenarios.json".to_string());
+    let registry = initial_scenarios(&scenarios_path).expect("Scenarios must load");
+    let metrics = Metrics::new(&REGISTRY, &path_labels(&registry));
+    let scenarios: Scenarios = Arc::new(RwLock::new(registry));
+    reload_on_hangup(scenarios_path, scenarios.clone(), metrics.clone());
+    let sessions = DeckSessions::new(IDLE_TIMEOUT);
+    expire_idle(sessions.clone(), "decks");
+    let games = GameSessions::new(IDLE_TIMEOUT);
+    expire_idle(games.clone(), "games");
+    let logger = warp::log("unleash-blackjack");
+
+    let shuffle = warp::path!("shuffle")
+        .and(warp::get())
+        .and(warp::query::<ShuffleQuery>())
+        .map(|q: ShuffleQuery| {
+            let seed = match q.seed.as_deref().map(parse_seed).transpose() {
+                Ok(seed) => seed.unwrap_or_else(random_seed),
+                Err(e) => return bad_request(format!("Invalid seed: {}", e)),
+            };
+            let decks = match deck_count(q.decks) {
+                Ok(decks) => decks,
+                Err(e) => return bad_request(e.to_string()),
+            };
+            let shuffled_deck = shuffled_shoe(decks, seed);
+            warp::reply::with_header(
+                warp::reply::json(&shuffled_deck),
+                SEED_HEADER,
+                seed.to_string(),
+            )
+            .into_response()
+        });
+
+    // A deck dealt a card at a time to streaming clients. The cards are drawn from a deck
+    // session, which is kept afterwards with every card dealt.
+    let deal_stream = {
+        let scenarios = scenarios.clone();
+        let sessions = sessions.clone();
+        warp::path!("deal" / "stream")
+            .and(warp::get())
+            .and(warp::query::<NewDeckQuery>())
+            .and(warp::query::<DealStreamQuery>())
+            .map(move |deck_query: NewDeckQuery, q: DealStreamQuery| {
+                let interval = match deal_interval(q.interval_ms) {
+                    Ok(interval) => interval,
+                    Err(message) => return bad_request(message),
+                };
+                let scenarios = scenarios.read().expect("Lock is not poisoned");
+                let (deck, seed) = match new_deck(&deck_query, &scenarios) {
+                    Ok(deck) => deck,
+                    Err(e) => return invalid(e.message, e.details),
+                };
+                let remaining = deck.remaining();
+                let dealt = sessions.open(deck).and_then(|id| {
+                    let cards = sessions.draw(&id, remaining)?.cards;
+                    Ok((cards, sessions.state(&id)?))
+                });
+                let (cards, state) = match dealt {
+                    Ok(dealt) => dealt,
+                    Err(e) => return session_error(e),
+                };
+                let events = deal_events(cards, state, interval);
+                let reply = warp::sse::reply(warp::sse::keep_alive().stream(events));
+                match seed {
+                    Some(seed) => warp::reply::with_header(reply, SEED_HEADER, seed.to_string())
+                        .into_response(),
+                    None => reply.into_response(),
+                }
+            })
+    };
+
+    // Decks dealt one call at a time, kept under an id until they are left idle for too long.
+    let open_deck = {
+        let scenarios = scenarios.clone();
+        let sessions = sessions.clone();
+        warp::path!("decks")
+            .and(warp::post())
+            .and(warp::query::<NewDeckQuery>())
+            .map(move |q: NewDeckQuery| {
+                let scenarios = scenarios.read().expect("Lock is not poisoned");
+                let (deck, seed) = match new_deck(&q, &scenarios) {
+                    Ok(deck) => deck,
+                    Err(e) => return invalid(e.message, e.details),
+                };
+                match sessions.open(deck).and_then(|id| sessions.state(&id)) {
+                    Ok(state) => {
+                        let location = format!("/decks/{}", state.deck_id);
+                        created(&state, location, seed)
+                    }
+                    Err(e) => session_error(e),
+                }
+            })
+    };
+    let deck_state = {
+        let sessions = sessions.clone();
+        warp::path!("decks" / String)
+            .and(warp::get())
+            .map(move |id: String| match sessions.state(&id) {
+                Ok(state) => warp::reply::json(&state).into_response(),
+                Err(e) => session_error(e),
+            })
+    };
+    let draw = {
+        let sessions = sessions.clone();
+        warp::path!("decks" / String / "draw")
+            .and(warp::get())
+            .and(warp::query::<CountQuery>())
+            .map(
+                move |id: String, q: CountQuery| match sessions.draw(&id, q.count.unwrap_or(1)) {
+                    Ok(drawn) => warp::reply::json(&drawn).into_response(),
+                    Err(e) => session_error(e),
+                },
+            )
+    };
+    let peek = {
+        let sessions = sessions.clone();
+        warp::path!("decks" / String / "peek")
+            .and(warp::get())
+            .and(warp::query::<CountQuery>())
+            .map(
+                move |id: String, q: CountQuery| match sessions.peek(&id, q.count.unwrap_or(1)) {
+                    Ok(top) => warp::reply::json(&top).into_response(),
+                    Err(e) => session_error(e),
+                },
+            )
+    };
+    let reshuffle = warp::path!("decks" / String / "shuffle")
+        .and(warp::post())
+        .and(warp::query::<SeedQuery>())
+        .map(move |id: String, q: SeedQuery| {
+            let seed = match q.seed.as_deref().map(parse_seed).transpose() {
+                Ok(seed) => seed.unwrap_or_else(random_seed),
+                Err(e) => return bad_request(format!("Invalid seed: {}", e)),
+            };
+            match sessions.shuffle(&id, seed) {
+                Ok(state) => warp::reply::with_header(
+                    warp::reply::json(&state),
+                    SEED_HEADER,
+                    seed.to_string(),
+                )
+                .into_response(),
+                Err(e) => session_error(e),
+            }
+        });
+
+    // One seat games the server deals, the client only makes the player's decisions.
+    let open_game = {
+        let scenarios = scenarios.clone();
+        let games = games.clone();
+        warp::path!("games")
+            .and(warp::post())
+            .and(warp::query::<NewDeckQuery>())
+            .and(warp::query::<NewGameQuery>())
+            .map(move |deck_query: NewDeckQuery, q: NewGameQuery| {
+                let rules = table_rules(
+                    q.rules.as_deref(),
+                    deck_query.order.as_deref(),
+                    deck_query.burn,
+                );
+                let rules = match rules {
+                    Ok(rules) => rules,
+                    Err(message) => return bad_request(message),
+                };
+                let scenarios = scenarios.read().expect("Lock is not poisoned");
+                let (deck, seed) = match new_deck(&deck_query, &scenarios) {
+                    Ok(deck) => deck,
+                    Err(e) => return invalid(e.message, e.details),
+                };
+                let game = match Game::new(deck, rules, 1) {
+                    Ok(game) => game,
+                    Err(e) => return bad_request(e.to_string()),
+                };
+                match games.open(game).and_then(|id| games.state(&id)) {
+                    Ok(state) => {
+                        let location = format!("/games/{}", state.game_id);
+                        created(&state, location, seed)
+                    }
+                    Err(e) => session_error(e),
+                }
+            })
+    };
+    let game_state = {
+        let games = games.clone();
+        warp::path!("games" / String)
+            .and(warp::get())
+            .map(move |id: String| match games.state(&id) {
+                Ok(state) => warp::reply::json(&state).into_response(),
+                Err(e) => session_error(e),
+            })
+    };
+    let insurance = {
+        let games = games.clone();
+        warp::path!("games" / String / "insurance")
+            .and(warp::post())
+            .and(warp::query::<InsuranceQuery>())
+            .map(move |id: String, q: InsuranceQuery| {
+                let Some(take) = q.take else {
+                    return bad_request("Say take=true or take=false".into());
+                };
+                match games.insure(&id, take) {
+                    Ok(state) => warp::reply::json(&state).into_response(),
+                    Err(e) => session_error(e),
+                }
+            })
+    };
+    let play = warp::path!("games" / String / String)
+        .and(warp::post())
+        .map(move |id: String, action: String| {
+            let Ok(action) = action.parse::<Action>() else {
+                let message = format!(
+                    "Unknown action '{}', use hit, stand, double, split or surrender",
+                    action
+                );
+                return bad_request(message);
+            };
+            match games.act(&id, action) {
+                Ok(state) => warp::reply::json(&state).into_response(),
+                Err(e) => session_error(e),
+            }
+        });
+
+    // Tables several players share over a WebSocket, each in their own seat.
+    let tables = Tables::default();
+    let table = warp::path!("ws")
+        .and(warp::ws())
+        .and(warp::query::<TableQuery>())
+        .map(move |ws: warp::ws::Ws, q: TableQuery| {
+            let seating = match q.table {
+                Some(id) => Seating::Join(id),
+                None => match new_table(&q) {
+                    Ok(table) => Seating::Open(Box::new(table)),
+                    Err(message) => return bad_request(message),
+                },
+            };
+            let tables = tables.clone();
+            ws.on_upgrade(move |socket| sit_at_table(socket, tables, seating))
+                .into_response()
+        });
+
+    let scenario_catalog = {
+        let scenarios = scenarios.clone();
+        warp::path!("scenarios").and(warp::get()).map(move || {
+            let scenarios = scenarios.read().expect("Lock is not poisoned");
+            warp::reply::json(&scenarios.listings()).into_response()
+        })
+    };
+
+    // A scenario deck served at `/{name}`, built from `decks` decks (`?decks=N`, default 1)
+    // and placed for the dealing order (`?order=paired|alternating&burn=N`, default paired).
+    let scenario = warp::path::param::<String>()
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<DecksQuery>())
+        .and_then(move |name: String, q: DecksQuery| {
+            let scenarios = scenarios.clone();
+            async move {
+                let scenarios = scenarios.read().expect("Lock is not poisoned");
+                let Some(scenario) = scenarios.get(&name) else {
+                    return Err(warp::reject::not_found());
+                };
+                let decks = match deck_count(q.decks) {
+                    Ok(decks) => decks,
+                    Err(e) => return Ok(bad_request(e.to_string())),
+                };
+                let order = match DealingOrder::parse(q.order.as_deref(), q.burn) {
+                    Ok(order) => order,
+                    Err(e) => return Ok(bad_request(e.to_string())),
+                };
+                Ok(match scenario.deck(order, decks) {
+                    Ok(deck) => warp::reply::json(&deck).into_response(),
+                    Err(errors) => invalid(
+                        format!("Can't deal scenario '{}'", name),
+                        errors.iter().map(ToString::to_string).collect(),
+                    ),
+                })
+            }
+        });
 
     let customdeck = warp::path!("custom")
         .and(warp::get())
         .and(warp::query::<BlackjackQuery>())
         .map(|q: BlackjackQuery| {
-            let cards = q
-                .cards
-                .split(',')
-                .map(Card::from_answer)
-                .collect::<Result<Vec<Card>, ()>>()
-                .unwrap_or_default();
-            let custom = complete_deck(cards);
-            warp::reply::json(&custom)
+            let (cards, _, decks) = match front_of_deck(&q) {
+                Ok(front) => front,
+                Err(e) => return invalid(e.message, e.details),
+            };
+            match complete_deck(cards, decks) {
+                Ok(custom) => warp::reply::json(&custom).into_response(),
+                Err(e) => deck_error(e),
+            }
+        });
+
+    // The interview game: interview rules, with the player hitting below 17.
+    let analyze_route = warp::path!("analyze")
+        .and(warp::get())
+        .and(warp::query::<BlackjackQuery>())
+        .and_then(|q: BlackjackQuery| async move {
+            let (cards, dealing, decks) = match front_of_deck(&q) {
+                Ok(front) => front,
+                Err(e) => return Ok::<_, Rejection>(invalid(e.message, e.details)),
+            };
+            let rules = Rules {
+                dealing,
+                ..Rules::interview()
+            };
+            let analysis = tokio::task::spawn_blocking(move || {
+                analyze(&cards, decks, rules, &Threshold::default())
+            })
+            .await
+            .expect("Analysis panicked");
+            Ok(match analysis {
+                Ok(analysis) => warp::reply::json(&analysis).into_response(),
+                Err(AnalysisError::Deck(e)) => deck_error(e),
+                Err(e) => bad_request(e.to_string()),
+            })
+        });
+
+    // Solves for a deck where the player hits below 17, under interview rules by default.
+    let solve_route = warp::path!("solve")
+        .and(warp::get())
+        .and(warp::query::<SolveQuery>())
+        .and_then(|q: SolveQuery| async move {
+            let rules = match table_rules(q.rules.as_deref(), q.order.as_deref(), q.burn) {
+                Ok(rules) => rules,
+                Err(message) => return Ok::<_, Rejection>(bad_request(message)),
+            };
+            let decks = match deck_count(q.decks) {
+                Ok(decks) => decks,
+                Err(e) => return Ok(bad_request(e.to_string())),
+            };
+            let goal = match Goal::parse(&q.goal) {
+                Ok(goal) => goal,
+                Err(errors) => {
+                    let details = errors.iter().map(ToString::to_string).collect();
+                    return Ok(invalid("Invalid goal".into(), details));
+                }
+            };
+            let solved = tokio::task::spawn_blocking(move || {
+                solve(&goal, rules, &Threshold::default(), decks)
+            })
+            .await
+            .expect("Solver panicked");
+            Ok(match solved {
+                Ok(deck) => warp::reply::json(&deck).into_response(),
+                Err(e) => bad_request(e.to_string()),
+            })
+        });
+
+    // A deck the bug model plays differently from the interview game.
+    let adversarial = warp::path!("adversarial")
+        .and(warp::get())
+        .and(warp::query::<AdversarialQuery>())
+        .and_then(|q: AdversarialQuery| async move {
+            let bug: BugModel = match q.bug.parse() {
+                Ok(bug) => bug,
+                Err(_) => {
+                    let known = catalog().map(|bug| bug.as_ref().to_string()).collect();
+                    let message = format!("Unknown bug model '{}'", q.bug);
+                    return Ok::<_, Rejection>(invalid(message, known));
+                }
+            };
+            let seed = match q.seed.as_deref().map(parse_seed).transpose() {
+                Ok(seed) => seed.unwrap_or_else(random_seed),
+                Err(e) => return Ok(bad_request(format!("Invalid seed: {}", e))),
+            };
+            let decks = match deck_count(q.decks) {
+                Ok(decks) => decks,
+                Err(e) => return Ok(bad_request(e.to_string())),
+            };
+            let found = tokio::task::spawn_blocking(move || {
+                let interview = Engine {
+                    rules: Rules::interview(),
+                    strategy: &Threshold::default(),
+                };
+                find_disagreement(&bug, &interview, decks, seed)
+            })
+            .await
+            .expect("Search panicked");
+            Ok(match found {
+                Ok(Some(found)) => warp::reply::with_header(
+                    warp::reply::json(&found.deck),
+                    SEED_HEADER,
+                    seed.to_string(),
+                )
+                .into_response(),
+                Ok(None) => bad_request(format!("No deck found that catches '{}'", q.bug)),
+                Err(e) => bad_request(e.to_string()),
+            })
         });
 
     let metrics_route = warp::path!("metrics").and(warp::get()).map(|| {
@@ -134,14 +842,23 @@ async fn main() {
     let routes = warp::any()
         .and(
             shuffle
-                .or(fouraces)
-                .or(playerblackjack)
-                .or(dealerblackjack)
-                .or(both_blackjack)
-                .or(tie21)
-                .or(playerbust)
-                .or(dealerbust)
                 .or(customdeck)
+                .or(analyze_route)
+                .or(solve_route)
+                .or(adversarial)
+                .or(open_deck)
+                .or(deck_state)
+                .or(draw)
+                .or(peek)
+                .or(reshuffle)
+                .or(deal_stream)
+                .or(open_game)
+                .or(game_state)
+                .or(insurance)
+                .or(play)
+                .or(table)
+                .or(scenario_catalog)
+                .or(scenario)
                 .or(metrics_route)
                 .or(health),
         )
@@ -1,9 +1,12 @@
+use std::sync::{Arc, RwLock};
+
 use prometheus::{HistogramOpts, HistogramVec, Registry};
 
 #[derive(Debug, Clone)]
 pub struct Metrics {
     http_timer: HistogramVec,
-    include_path_labels: Vec<String>,
+    /// Shared between clones, so the labels can change while a clone logs requests.
+    include_path_labels: Arc<RwLock<Vec<String>>>,
 }
 
 impl Metrics {
@@ -18,14 +21,26 @@ impl Metrics {
 
         Self {
             http_timer: internal_http_timer,
-            include_path_labels: include_path_labels.to_owned(),
+            include_path_labels: Arc::new(RwLock::new(include_path_labels.to_owned())),
         }
     }
 
+    /// Replaces the path segments kept in the path label, for this and every clone.
+    pub fn set_path_labels(&self, include_path_labels: &[String]) {
+        *self
+            .include_path_labels
+            .write()
+            .expect("Lock is not poisoned") = include_path_labels.to_owned();
+    }
+
     fn sanitize_path_segments(&self, path: &str) -> String {
+        let include_path_labels = self
+            .include_path_labels
+            .read()
+            .expect("Lock is not poisoned");
         let path_segments: Vec<&str> = path.split('/').collect();
         path_segments.iter().fold(String::new(), |acc, &path| {
-            if self.include_path_labels.contains(&path.to_string()) {
+            if include_path_labels.contains(&path.to_string()) {
                 format!("{}/{}", acc, path)
             } else if path.is_empty() {
                 acc.to_string()
@@ -64,7 +79,6 @@ impl Metrics {
         self.http_timer
             .with_label_values(&[method, path, info.status().as_u16().to_string()])
             .observe(info.elapsed().as_secs_f64());
-
     }
 }
 
@@ -111,7 +125,6 @@ mod test {
 
     #[test]
     fn test_totally_wrong_path() {
-
         let registry: Registry = Registry::new();
         let path_includes: Vec<String> = vec![String::from("users"), String::from("registration")];
 
@@ -121,4 +134,16 @@ mod test {
 
         assert_eq!("/*/*/*/*/*".to_string(), sanitized_path)
     }
+
+    #[test]
+    fn test_path_labels_change_for_every_clone() {
+        let registry: Registry = Registry::new();
+        let metrics = Metrics::new(&registry, &[String::from("users")]);
+        let logging = metrics.clone();
+
+        metrics.set_path_labels(&[String::from("registration")]);
+        let sanitized_path = logging.sanitize_path_segments("/users/registration");
+
+        assert_eq!("/*/registration".to_string(), sanitized_path)
+    }
 }
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// The top of a deck, either as `cards` in the order they are dealt or as the cards for each
+/// role, see [`logic::dealing::Roles`]. The roles are placed for the dealing order `order`
+/// (`paired` or `alternating`) after `burn` burnt cards.
+#[derive(Serialize, Deserialize)]
+pub struct BlackjackQuery {
+    pub cards: Option<String>,
+    pub player: Option<String>,
+    pub dealer: Option<String>,
+    pub player_hits: Option<String>,
+    pub dealer_hits: Option<String>,
+    pub order: Option<String>,
+    pub burn: Option<usize>,
+    pub decks: Option<usize>,
+}
+
+impl BlackjackQuery {
+    pub fn has_roles(&self) -> bool {
+        [
+            &self.player,
+            &self.dealer,
+            &self.player_hits,
+            &self.dealer_hits,
+        ]
+        .iter()
+        .any(|role| role.is_some())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShuffleQuery {
+    pub seed: Option<String>,
+    pub decks: Option<usize>,
+}
+
+/// A scenario deck, placed for the dealing order `order` after `burn` burnt cards.
+#[derive(Serialize, Deserialize)]
+pub struct DecksQuery {
+    pub order: Option<String>,
+    pub burn: Option<usize>,
+    pub decks: Option<usize>,
+}
+
+/// A deck to deal from one call at a time: a `scenario`, a shuffle of `seed`, or `cards` on
+/// top of the rest in standard order. A random shuffle when none is given.
+#[derive(Serialize, Deserialize)]
+pub struct NewDeckQuery {
+    pub scenario: Option<String>,
+    pub seed: Option<String>,
+    pub cards: Option<String>,
+    pub order: Option<String>,
+    pub burn: Option<usize>,
+    pub decks: Option<usize>,
+}
+
+/// The table a one seat game is dealt at, the `rules` preset, interview when not given. The
+/// deck comes from the same query string read as a [`NewDeckQuery`].
+#[derive(Serialize, Deserialize)]
+pub struct NewGameQuery {
+    pub rules: Option<String>,
+}
+
+/// How fast `/deal/stream` deals, a card every `interval_ms` milliseconds. The deck comes from
+/// the same query string read as a [`NewDeckQuery`].
+#[derive(Serialize, Deserialize)]
+pub struct DealStreamQuery {
+    pub interval_ms: Option<u64>,
+}
+
+/// A seat at the table `table`, or at a new table with the `rules` preset, interview when
+/// not given, dealing from a shoe of `decks` decks shuffled from `seed`.
+#[derive(Serialize, Deserialize)]
+pub struct TableQuery {
+    pub table: Option<String>,
+    pub rules: Option<String>,
+    pub order: Option<String>,
+    pub burn: Option<usize>,
+    pub seed: Option<String>,
+    pub decks: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct InsuranceQuery {
+    pub take: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CountQuery {
+    pub count: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SeedQuery {
+    pub seed: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AdversarialQuery {
+    pub bug: String,
+    pub seed: Option<String>,
+    pub decks: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SolveQuery {
+    pub goal: String,
+    /// A rules preset, interview when not given.
+    pub rules: Option<String>,
+    pub order: Option<String>,
+    pub burn: Option<usize>,
+    pub decks: Option<usize>,
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use logic::deck_generator::random_seed;
+use logic::error::GameError;
+
+/// How long a deck or a game nobody uses is kept.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// The most sessions kept at once, so clients that never finish can't fill up the memory.
+pub const MAX_SESSIONS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    /// There is no session with the id, or it expired.
+    NotFound(String),
+    TooManySessions,
+    InvalidCount(usize),
+    NotEnoughCards {
+        remaining: usize,
+        count: usize,
+    },
+    Game(GameError),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::NotFound(id) => write!(f, "No session '{}', it may have expired", id),
+            SessionError::TooManySessions => write!(
+                f,
+                "Too many sessions open, at most {}, try again later",
+                MAX_SESSIONS
+            ),
+            SessionError::InvalidCount(count) => {
+                write!(f, "Invalid count {}, must be at least 1", count)
+            }
+            SessionError::NotEnoughCards { remaining, count } => write!(
+                f,
+                "Can't take {} cards, only {} left, shuffle the deck to start over",
+                count, remaining
+            ),
+            SessionError::Game(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<GameError> for SessionError {
+    fn from(e: GameError) -> Self {
+        SessionError::Game(e)
+    }
+}
+
+#[derive(Debug)]
+struct Session<T> {
+    value: T,
+    last_used: Instant,
+}
+
+/// What clients keep on the server between calls, by id, until it is left idle for longer
+/// than the timeout. Clones share the sessions.
+#[derive(Debug)]
+pub struct Sessions<T> {
+    sessions: Arc<Mutex<HashMap<String, Session<T>>>>,
+    idle_timeout: Duration,
+}
+
+impl<T> Clone for Sessions<T> {
+    fn clone(&self) -> Self {
+        Sessions {
+            sessions: self.sessions.clone(),
+            idle_timeout: self.idle_timeout,
+        }
+    }
+}
+
+impl<T> Sessions<T> {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Sessions {
+            sessions: Arc::default(),
+            idle_timeout,
+        }
+    }
+
+    /// Keeps `value` under a new random id, which is returned.
+    pub fn open(&self, value: T) -> Result<String, SessionError> {
+        let mut sessions = self.sessions.lock().expect("Lock is not poisoned");
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(SessionError::TooManySessions);
+        }
+        let id = loop {
+            let id = format!("{:016x}", random_seed());
+            if !sessions.contains_key(&id) {
+                break id;
+            }
+        };
+        let last_used = Instant::now();
+        sessions.insert(id.clone(), Session { value, last_used });
+        Ok(id)
+    }
+
+    /// Runs `f` on the session with `id` if it hasn't expired, counting that as a use.
+    pub fn with<R>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut T) -> Result<R, SessionError>,
+    ) -> Result<R, SessionError> {
+        let mut sessions = self.sessions.lock().expect("Lock is not poisoned");
+        match sessions.get_mut(id) {
+            Some(session) if session.last_used.elapsed() < self.idle_timeout => {
+                session.last_used = Instant::now();
+                f(&mut session.value)
+            }
+            Some(_) => {
+                sessions.remove(id);
+                Err(SessionError::NotFound(id.to_string()))
+            }
+            None => Err(SessionError::NotFound(id.to_string())),
+        }
+    }
+
+    /// Drops every session left idle for longer than the timeout, returning how many went.
+    pub fn expire(&self) -> usize {
+        let mut sessions = self.sessions.lock().expect("Lock is not poisoned");
+        let before = sessions.len();
+        sessions.retain(|_, session| session.last_used.elapsed() < self.idle_timeout);
+        before - sessions.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sessions_are_kept_by_id() {
+        let sessions = Sessions::new(IDLE_TIMEOUT);
+        let id = sessions.open(1).unwrap();
+        sessions
+            .with(&id, |value| {
+                *value += 1;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(sessions.with(&id, |value| Ok(*value)), Ok(2));
+        assert_eq!(
+            sessions.with("nope", |value| Ok(*value)),
+            Err(SessionError::NotFound("nope".into()))
+        );
+    }
+
+    #[test]
+    fn idle_sessions_expire() {
+        let sessions = Sessions::new(Duration::ZERO);
+        let id = sessions.open(()).unwrap();
+        assert_eq!(sessions.expire(), 1);
+        assert_eq!(
+            sessions.with(&id, |_| Ok(())),
+            Err(SessionError::NotFound(id.clone()))
+        );
+
+        let kept = Sessions::new(IDLE_TIMEOUT);
+        let id = kept.open(()).unwrap();
+        assert_eq!(kept.expire(), 0);
+        assert!(kept.with(&id, |_| Ok(())).is_ok());
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use logic::deck_generator::random_seed;
+use logic::table::{Table, TableEvent, TableMessage};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A table and a channel to each player seated at it.
+#[derive(Debug)]
+struct Room {
+    table: Table,
+    players: HashMap<usize, UnboundedSender<String>>,
+}
+
+impl Room {
+    fn tell(&self, seat: usize, event: &TableEvent) {
+        if let Some(player) = self.players.get(&seat) {
+            // A player whose connection went is about to leave
+            let _ = player.send(to_json(event));
+        }
+    }
+
+    fn tell_everyone(&self, events: &[TableEvent]) {
+        for event in events {
+            let event = to_json(event);
+            for player in self.players.values() {
+                let _ = player.send(event.clone());
+            }
+        }
+    }
+}
+
+fn to_json(event: &TableEvent) -> String {
+    serde_json::to_string(event).expect("Events serialize")
+}
+
+/// The tables played over `/ws`, by id. Every player is sent the events of their table as JSON
+/// text. Clones share the tables.
+#[derive(Debug, Clone, Default)]
+pub struct Tables {
+    rooms: Arc<Mutex<HashMap<String, Room>>>,
+}
+
+impl Tables {
+    /// Seats `player` at `table`, kept under a new random id. Returns the id and the seat.
+    pub fn open(&self, table: Table, player: UnboundedSender<String>) -> (String, usize) {
+        let mut rooms = self.rooms.lock().expect("Lock is not poisoned");
+        let id = loop {
+            let id = format!("{:016x}", random_seed());
+            if !rooms.contains_key(&id) {
+                break id;
+            }
+        };
+        let room = Room {
+            table,
+            players: HashMap::new(),
+        };
+        rooms.insert(id.clone(), room);
+        drop(rooms);
+        let seat = self.join(&id, player).expect("A new table has free seats");
+        (id, seat)
+    }
+
+    /// Seats `player` at the table `id`, or says why they can't sit there.
+    pub fn join(&self, id: &str, player: UnboundedSender<String>) -> Result<usize, String> {
+        let mut rooms = self.rooms.lock().expect("Lock is not poisoned");
+        let room = rooms
+            .get_mut(id)
+            .ok_or_else(|| format!("No table '{}'", id))?;
+        let (seat, events) = room.table.join().map_err(|e| e.to_string())?;
+        room.players.insert(seat, player);
+        room.tell(
+            seat,
+            &TableEvent::Seated {
+                table: id.to_string(),
+                seat,
+            },
+        );
+        room.tell_everyone(&events);
+        Ok(seat)
+    }
+
+    /// Plays the JSON `message` of the player in `seat`, telling everybody what happened, or
+    /// only the player why it was refused.
+    pub fn handle(&self, id: &str, seat: usize, message: &str) {
+        let mut rooms = self.rooms.lock().expect("Lock is not poisoned");
+        let Some(room) = rooms.get_mut(id) else {
+            return;
+        };
+        let played = serde_json::from_str::<TableMessage>(message)
+            .map_err(|e| format!("Invalid message: {}", e))
+            .and_then(|message| room.table.handle(seat, message).map_err(|e| e.to_string()));
+        match played {
+            Ok(events) => room.tell_everyone(&events),
+            Err(message) => room.tell(seat, &TableEvent::Error { message }),
+        }
+    }
+
+    /// Frees the seat of a player who went, closing the table once nobody is left.
+    pub fn leave(&self, id: &str, seat: usize) {
+        let mut rooms = self.rooms.lock().expect("Lock is not poisoned");
+        let Some(room) = rooms.get_mut(id) else {
+            return;
+        };
+        room.players.remove(&seat);
+        let events = room.table.leave(seat);
+        room.tell_everyone(&events);
+        if room.table.is_empty() {
+            rooms.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logic::deck_generator::shoe;
+    use logic::rules::Rules;
+    use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+    fn events(player: &mut UnboundedReceiver<String>) -> Vec<String> {
+        let mut events = vec![];
+        while let Ok(event) = player.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn everybody_at_the_table_hears_what_happens() {
+        let tables = Tables::default();
+        let (first, mut first_events) = unbounded_channel();
+        let (second, mut second_events) = unbounded_channel();
+        let (id, seat) = tables.open(Table::new(Rules::interview(), shoe(1), 1), first);
+        assert_eq!(seat, 0);
+        assert_eq!(tables.join(&id, second), Ok(1));
+        assert_eq!(
+            events(&mut first_events),
+            [
+                format!(r#"{{"event":"seated","table":"{}","seat":0}}"#, id),
+                r#"{"event":"joined","seat":0}"#.to_string(),
+                r#"{"event":"joined","seat":1}"#.to_string(),
+            ]
+        );
+        events(&mut second_events);
+
+        tables.handle(&id, 1, r#"{"action":"deal"}"#);
+        let dealt = events(&mut first_events);
+        assert_eq!(dealt, events(&mut second_events));
+        assert_eq!(dealt[0], r#"{"event":"round_started","seats":[0,1]}"#);
+
+        tables.handle(&id, 1, r#"{"action":"hit"}"#);
+        tables.handle(&id, 1, "hit");
+        assert!(events(&mut first_events).is_empty());
+        assert_eq!(
+            events(&mut second_events),
+            [
+                r#"{"event":"error","message":"It's not your turn"}"#,
+                r#"{"event":"error","message":"Invalid message: expected value at line 1 column 1"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn the_table_closes_when_the_last_player_leaves() {
+        let tables = Tables::default();
+        let (player, _events) = unbounded_channel();
+        let (id, seat) = tables.open(Table::new(Rules::interview(), shoe(1), 1), player);
+        tables.leave(&id, seat);
+
+        let (player, _events) = unbounded_channel();
+        assert_eq!(tables.join(&id, player), Err(format!("No table '{}'", id)));
+    }
+}